@@ -62,6 +62,7 @@ END;
 $$ language 'plpgsql';
 
 -- Create trigger for updated_at
+DROP TRIGGER IF EXISTS update_bail_applications_updated_at ON bail_applications;
 CREATE TRIGGER update_bail_applications_updated_at 
     BEFORE UPDATE ON bail_applications 
     FOR EACH ROW 
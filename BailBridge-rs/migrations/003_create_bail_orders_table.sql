@@ -0,0 +1,17 @@
+-- Create bail_orders table
+CREATE TABLE IF NOT EXISTS bail_orders (
+    id UUID PRIMARY KEY,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    judge_id UUID NOT NULL REFERENCES users(id),
+    
+    decision VARCHAR(50) NOT NULL, -- granted, rejected, partly_granted
+    order_text TEXT NOT NULL,
+    bail_amount BIGINT, -- in rupees, only for granted orders
+    order_date DATE NOT NULL,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_bail_orders_application_id ON bail_orders(application_id);
+CREATE INDEX IF NOT EXISTS idx_bail_orders_judge_id ON bail_orders(judge_id);
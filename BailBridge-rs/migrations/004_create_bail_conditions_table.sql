@@ -0,0 +1,19 @@
+-- Create bail_conditions table
+CREATE TABLE IF NOT EXISTS bail_conditions (
+    id UUID PRIMARY KEY,
+    order_id UUID NOT NULL REFERENCES bail_orders(id) ON DELETE CASCADE,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    condition_type VARCHAR(50) NOT NULL, -- surrender_passport, report_to_police, restrict_travel, no_witness_contact, other
+    description TEXT NOT NULL,
+    frequency VARCHAR(20), -- once, daily, weekly, fortnightly, monthly
+    start_date DATE NOT NULL,
+    end_date DATE,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_bail_conditions_order_id ON bail_conditions(order_id);
+CREATE INDEX IF NOT EXISTS idx_bail_conditions_application_id ON bail_conditions(application_id);
+CREATE INDEX IF NOT EXISTS idx_bail_conditions_type_frequency ON bail_conditions(condition_type, frequency);
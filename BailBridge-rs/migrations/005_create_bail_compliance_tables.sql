@@ -0,0 +1,33 @@
+-- Create bail_check_ins table
+CREATE TABLE IF NOT EXISTS bail_check_ins (
+    id UUID PRIMARY KEY,
+    condition_id UUID NOT NULL REFERENCES bail_conditions(id) ON DELETE CASCADE,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    checked_in_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    police_station VARCHAR(255) NOT NULL,
+    officer_name VARCHAR(255) NOT NULL,
+    verified BOOLEAN NOT NULL DEFAULT FALSE,
+    verified_by UUID REFERENCES users(id),
+    recorded_by UUID NOT NULL REFERENCES users(id),
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create bail_violations table, one row per missed reporting period
+CREATE TABLE IF NOT EXISTS bail_violations (
+    id UUID PRIMARY KEY,
+    condition_id UUID NOT NULL REFERENCES bail_conditions(id) ON DELETE CASCADE,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    period_start DATE NOT NULL,
+    period_end DATE NOT NULL,
+    
+    detected_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (condition_id, period_start)
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_bail_check_ins_condition_id ON bail_check_ins(condition_id, checked_in_at);
+CREATE INDEX IF NOT EXISTS idx_bail_check_ins_application_id ON bail_check_ins(application_id);
+CREATE INDEX IF NOT EXISTS idx_bail_violations_application_id ON bail_violations(application_id);
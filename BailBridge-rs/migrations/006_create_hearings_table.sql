@@ -0,0 +1,31 @@
+-- Create hearings table
+CREATE TABLE IF NOT EXISTS hearings (
+    id UUID PRIMARY KEY,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    judge_id UUID REFERENCES users(id),
+    
+    court VARCHAR(255) NOT NULL,
+    courtroom VARCHAR(50) NOT NULL,
+    scheduled_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    purpose TEXT NOT NULL,
+    
+    status VARCHAR(50) NOT NULL DEFAULT 'scheduled', -- scheduled, adjourned, concluded
+    adjournment_reason TEXT,
+    outcome TEXT,
+    
+    scheduled_by UUID NOT NULL REFERENCES users(id),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_hearings_application_id ON hearings(application_id);
+CREATE INDEX IF NOT EXISTS idx_hearings_judge_scheduled_at ON hearings(judge_id, scheduled_at);
+CREATE INDEX IF NOT EXISTS idx_hearings_court_scheduled_at ON hearings(court, scheduled_at);
+
+-- Create trigger for updated_at
+DROP TRIGGER IF EXISTS update_hearings_updated_at ON hearings;
+CREATE TRIGGER update_hearings_updated_at 
+    BEFORE UPDATE ON hearings 
+    FOR EACH ROW 
+    EXECUTE FUNCTION update_updated_at_column();
@@ -0,0 +1,19 @@
+-- Create documents table
+CREATE TABLE IF NOT EXISTS documents (
+    id UUID PRIMARY KEY,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    document_type VARCHAR(50) NOT NULL, -- fir_copy, arrest_memo, medical_certificate, surety_id_proof, previous_bail_order, other
+    file_name VARCHAR(255) NOT NULL,
+    storage_key TEXT NOT NULL,
+    sha256 CHAR(64) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    mime_type VARCHAR(100) NOT NULL,
+    uploaded_by UUID NOT NULL REFERENCES users(id),
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_documents_application_id ON documents(application_id);
+CREATE INDEX IF NOT EXISTS idx_documents_sha256 ON documents(sha256);
@@ -0,0 +1,30 @@
+-- Create sureties table
+CREATE TABLE IF NOT EXISTS sureties (
+    id UUID PRIMARY KEY,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    name VARCHAR(255) NOT NULL,
+    relation VARCHAR(100) NOT NULL,
+    address TEXT NOT NULL,
+    id_document_number VARCHAR(100) NOT NULL,
+    solvency_value BIGINT NOT NULL, -- value of property/solvency in rupees
+    phone_number VARCHAR(20) NOT NULL,
+    
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_sureties_application_id ON sureties(application_id);
+CREATE INDEX IF NOT EXISTS idx_sureties_id_document_number ON sureties(id_document_number);
+
+-- Create trigger for updated_at
+DROP TRIGGER IF EXISTS update_sureties_updated_at ON sureties;
+CREATE TRIGGER update_sureties_updated_at 
+    BEFORE UPDATE ON sureties 
+    FOR EACH ROW 
+    EXECUTE FUNCTION update_updated_at_column();
+
+-- bail_applications.surety_details is kept as legacy free text so existing
+-- rows stay readable; new applications record sureties in this table.
+COMMENT ON COLUMN bail_applications.surety_details IS 'Legacy free-text surety details, superseded by the sureties table';
@@ -0,0 +1,33 @@
+-- Drafts are saved with partial fields, so the case columns may be NULL
+-- until the application is submitted.
+ALTER TABLE bail_applications
+    ALTER COLUMN applicant_name DROP NOT NULL,
+    ALTER COLUMN father_husband_name DROP NOT NULL,
+    ALTER COLUMN age DROP NOT NULL,
+    ALTER COLUMN gender DROP NOT NULL,
+    ALTER COLUMN address DROP NOT NULL,
+    ALTER COLUMN phone_number DROP NOT NULL,
+    ALTER COLUMN fir_number DROP NOT NULL,
+    ALTER COLUMN police_station DROP NOT NULL,
+    ALTER COLUMN district DROP NOT NULL,
+    ALTER COLUMN state DROP NOT NULL,
+    ALTER COLUMN date_of_arrest DROP NOT NULL,
+    ALTER COLUMN sections_applied DROP NOT NULL,
+    ALTER COLUMN case_description DROP NOT NULL,
+    ALTER COLUMN bail_type DROP NOT NULL;
+
+-- Every application past draft must still have the full set of fields
+ALTER TABLE bail_applications
+    DROP CONSTRAINT IF EXISTS chk_bail_applications_submitted_fields,
+    ADD CONSTRAINT chk_bail_applications_submitted_fields CHECK (
+        status = 'draft' OR (
+            applicant_name IS NOT NULL AND father_husband_name IS NOT NULL AND age IS NOT NULL
+            AND gender IS NOT NULL AND address IS NOT NULL AND phone_number IS NOT NULL
+            AND fir_number IS NOT NULL AND police_station IS NOT NULL AND district IS NOT NULL
+            AND state IS NOT NULL AND date_of_arrest IS NOT NULL AND sections_applied IS NOT NULL
+            AND case_description IS NOT NULL AND bail_type IS NOT NULL
+            AND previous_bail_applications IS NOT NULL
+        )
+    );
+
+CREATE INDEX IF NOT EXISTS idx_bail_applications_user_status ON bail_applications(user_id, status);
@@ -0,0 +1,16 @@
+-- Create application_revisions table, one row per changed field
+CREATE TABLE IF NOT EXISTS application_revisions (
+    id UUID PRIMARY KEY,
+    application_id UUID NOT NULL REFERENCES bail_applications(id) ON DELETE CASCADE,
+    
+    field_name VARCHAR(100) NOT NULL,
+    old_value JSONB,
+    new_value JSONB,
+    
+    changed_by UUID NOT NULL REFERENCES users(id),
+    changed_by_role VARCHAR(50) NOT NULL,
+    changed_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_application_revisions_application_id ON application_revisions(application_id, changed_at);
@@ -0,0 +1,36 @@
+-- Create audit_log table: append-only and hash-chained
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    occurred_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    
+    -- No foreign keys: entries must outlive the users and cases they mention
+    actor_id UUID,
+    actor_role VARCHAR(50),
+    action VARCHAR(255) NOT NULL, -- e.g. "GET /bail-applications/{application_number}"
+    application_number VARCHAR(50),
+    ip_address VARCHAR(64),
+    user_agent TEXT,
+    outcome SMALLINT NOT NULL, -- HTTP status code
+    
+    prev_hash CHAR(64) NOT NULL,
+    hash CHAR(64) NOT NULL UNIQUE
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor_id ON audit_log(actor_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_application_number ON audit_log(application_number);
+CREATE INDEX IF NOT EXISTS idx_audit_log_occurred_at ON audit_log(occurred_at DESC);
+
+-- Reject any attempt to rewrite history through the application's role
+CREATE OR REPLACE FUNCTION reject_audit_log_change()
+RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ language 'plpgsql';
+
+DROP TRIGGER IF EXISTS audit_log_append_only ON audit_log;
+CREATE TRIGGER audit_log_append_only
+    BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW
+    EXECUTE FUNCTION reject_audit_log_change();
@@ -0,0 +1,20 @@
+-- Lawyers and judges must be vetted before they can see case data
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_schema = current_schema() AND table_name = 'users' AND column_name = 'verification_status'
+    ) THEN
+        ALTER TABLE users
+            ADD COLUMN verification_status VARCHAR(20) NOT NULL DEFAULT 'verified'; -- pending, verified, rejected
+
+        -- Lawyer and judge accounts so far were self-registered: hold them until an admin reviews them.
+        -- Only done when the column is added, so re-running this file never undoes an admin's decision
+        UPDATE users SET verification_status = 'pending' WHERE role IN ('lawyer', 'judge');
+    END IF;
+END $$;
+
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS bar_enrolment_number VARCHAR(100) UNIQUE;
+
+CREATE INDEX IF NOT EXISTS idx_users_verification_status ON users(verification_status);
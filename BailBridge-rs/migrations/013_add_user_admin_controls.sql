@@ -0,0 +1,18 @@
+-- Account controls used by the admin user management API
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS suspended_at TIMESTAMP WITH TIME ZONE,
+    ADD COLUMN IF NOT EXISTS password_reset_required BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Create admin_actions table: what an admin did to which account
+CREATE TABLE IF NOT EXISTS admin_actions (
+    id UUID PRIMARY KEY,
+    admin_id UUID NOT NULL REFERENCES users(id),
+    target_user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    action VARCHAR(50) NOT NULL, -- create_user, set_verification, suspend, reactivate, change_role, reset_password
+    details JSONB NOT NULL DEFAULT '{}'::jsonb,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_admin_actions_target_user_id ON admin_actions(target_user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_admin_actions_admin_id ON admin_actions(admin_id);
@@ -0,0 +1,10 @@
+-- Create rate_limit_buckets table: token buckets shared by every server when RATE_LIMIT_BACKEND=postgres
+CREATE TABLE IF NOT EXISTS rate_limit_buckets (
+    key VARCHAR(255) PRIMARY KEY, -- e.g. 'auth:ip:203.0.113.7' or 'role:lawyer:<user id>'
+    tokens DOUBLE PRECISION NOT NULL,
+    allowed BOOLEAN NOT NULL, -- whether the last request taken from the bucket got through
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_rate_limit_buckets_updated_at ON rate_limit_buckets(updated_at);
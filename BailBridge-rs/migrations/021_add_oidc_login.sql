@@ -0,0 +1,25 @@
+-- Create external_identities table: accounts at an OpenID Connect provider linked to a user
+CREATE TABLE IF NOT EXISTS external_identities (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    provider VARCHAR(50) NOT NULL, -- name from OIDC_PROVIDERS
+    subject VARCHAR(255) NOT NULL, -- the provider's `sub` claim
+    email VARCHAR(255),
+    last_login_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (provider, subject)
+);
+
+-- Create oidc_login_states table: one row per login sent to a provider, until it comes back
+CREATE TABLE IF NOT EXISTS oidc_login_states (
+    id UUID PRIMARY KEY,
+    provider VARCHAR(50) NOT NULL,
+    state_hash CHAR(64) NOT NULL UNIQUE, -- SHA-256 of the `state` parameter
+    code_verifier VARCHAR(128) NOT NULL, -- PKCE verifier for the code exchange
+    nonce VARCHAR(64) NOT NULL,
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_external_identities_user_id ON external_identities(user_id);
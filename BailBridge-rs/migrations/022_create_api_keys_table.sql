@@ -0,0 +1,18 @@
+-- Create api_keys table: long-lived credentials for integrations, stored only as hashes
+CREATE TABLE IF NOT EXISTS api_keys (
+    id UUID PRIMARY KEY,
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    name VARCHAR(100) NOT NULL,
+    prefix VARCHAR(16) NOT NULL UNIQUE, -- public part of the key, used to look it up
+    secret_hash CHAR(64) NOT NULL, -- SHA-256 of the secret part
+    read_only BOOLEAN NOT NULL DEFAULT FALSE,
+    endpoints TEXT[], -- "METHOD /route" or "/route" patterns the key may call; NULL for any
+    expires_at TIMESTAMP WITH TIME ZONE,
+    last_used_at TIMESTAMP WITH TIME ZONE,
+    last_used_ip VARCHAR(64),
+    revoked_at TIMESTAMP WITH TIME ZONE,
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_api_keys_user_id ON api_keys(user_id);
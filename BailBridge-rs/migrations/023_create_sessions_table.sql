@@ -0,0 +1,14 @@
+-- Create sessions table: one row per login, shared by every token issued under it
+CREATE TABLE IF NOT EXISTS sessions (
+    id UUID PRIMARY KEY, -- the refresh token family, sent as `sid` in access tokens
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    user_agent TEXT,
+    ip_address VARCHAR(64), -- where the login came from
+    last_seen_ip VARCHAR(64),
+    created_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    revoked_at TIMESTAMP WITH TIME ZONE
+);
+
+-- Create indexes for faster lookups
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id);
@@ -0,0 +1,11 @@
+-- Profile details shown under /me
+ALTER TABLE users
+    ADD COLUMN IF NOT EXISTS display_name VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS phone VARCHAR(20),
+    -- Lawyers only; the enrolment number is bar_enrolment_number
+    ADD COLUMN IF NOT EXISTS practice_courts TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS languages TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN IF NOT EXISTS specialisations TEXT[] NOT NULL DEFAULT '{}',
+    -- Judges only
+    ADD COLUMN IF NOT EXISTS court VARCHAR(255),
+    ADD COLUMN IF NOT EXISTS designation VARCHAR(100);
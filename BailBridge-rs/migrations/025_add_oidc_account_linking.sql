@@ -0,0 +1,4 @@
+-- Set when a signed-in user links a provider account to their own account,
+-- rather than logging in with it
+ALTER TABLE oidc_login_states
+    ADD COLUMN IF NOT EXISTS link_user_id UUID REFERENCES users(id) ON DELETE CASCADE;
@@ -0,0 +1,3 @@
+-- The new password of a forced reset, held until the second factor checks out
+ALTER TABLE login_challenges
+    ADD COLUMN IF NOT EXISTS new_password_hash VARCHAR(255);
@@ -17,12 +17,37 @@ if (-Not $databaseUrl) {
     exit 1
 }
 
-# Run the migration using psql
-psql $databaseUrl -f "migrations\002_create_bail_applications_table.sql"
-
-if ($LASTEXITCODE -eq 0) {
-    Write-Host "✓ Bail applications table created successfully!" -ForegroundColor Green
-} else {
-    Write-Host "✗ Migration failed!" -ForegroundColor Red
+# Applied migrations are recorded in schema_migrations so each file runs once.
+# Every file is also safe to re-run, so a database set up before the table
+# existed is brought up to date the first time it is tracked.
+psql $databaseUrl -q -v ON_ERROR_STOP=1 -c "CREATE TABLE IF NOT EXISTS schema_migrations (version VARCHAR(255) PRIMARY KEY, applied_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP)"
+if ($LASTEXITCODE -ne 0) {
+    Write-Host "✗ Could not create schema_migrations" -ForegroundColor Red
     exit 1
 }
+
+# Run the pending migrations in order (001 is the users table, see setup_database.sql)
+$migrations = Get-ChildItem "migrations\*.sql" | Where-Object { $_.Name -notlike "001_*" } | Sort-Object Name
+
+foreach ($migration in $migrations) {
+    $version = $migration.BaseName
+    $applied = psql $databaseUrl -tA -v ON_ERROR_STOP=1 -c "SELECT 1 FROM schema_migrations WHERE version = '$version'"
+    if ($LASTEXITCODE -ne 0) {
+        Write-Host "✗ Could not read schema_migrations" -ForegroundColor Red
+        exit 1
+    }
+    if ("$applied".Trim() -eq "1") {
+        continue
+    }
+
+    # The migration and its record commit together, or not at all
+    Write-Host "Applying $($migration.Name)..." -ForegroundColor Cyan
+    psql $databaseUrl -v ON_ERROR_STOP=1 --single-transaction -f $migration.FullName -c "INSERT INTO schema_migrations (version) VALUES ('$version')"
+
+    if ($LASTEXITCODE -ne 0) {
+        Write-Host "✗ Migration failed: $($migration.Name)" -ForegroundColor Red
+        exit 1
+    }
+}
+
+Write-Host "✓ Bail application tables are up to date!" -ForegroundColor Green
@@ -11,12 +11,36 @@ else
     exit 1
 fi
 
-# Run the migration
-psql "$DATABASE_URL" -f migrations/002_create_bail_applications_table.sql
-
-if [ $? -eq 0 ]; then
-    echo "✓ Bail applications table created successfully!"
-else
-    echo "✗ Migration failed!"
+# Applied migrations are recorded in schema_migrations so each file runs once.
+# Every file is also safe to re-run, so a database set up before the table
+# existed is brought up to date the first time it is tracked.
+psql "$DATABASE_URL" -q -v ON_ERROR_STOP=1 -c "CREATE TABLE IF NOT EXISTS schema_migrations (version VARCHAR(255) PRIMARY KEY, applied_at TIMESTAMP WITH TIME ZONE DEFAULT CURRENT_TIMESTAMP)"
+if [ $? -ne 0 ]; then
+    echo "✗ Could not create schema_migrations"
     exit 1
 fi
+
+# Run the pending migrations in order (001 is the users table, see setup_database.sql)
+for migration in $(ls migrations/*.sql | grep -v '/001_' | sort); do
+    version=$(basename "$migration" .sql)
+    applied=$(psql "$DATABASE_URL" -tA -v ON_ERROR_STOP=1 -c "SELECT 1 FROM schema_migrations WHERE version = '$version'")
+    if [ $? -ne 0 ]; then
+        echo "✗ Could not read schema_migrations"
+        exit 1
+    fi
+    if [ "$applied" = "1" ]; then
+        continue
+    fi
+
+    # The migration and its record commit together, or not at all
+    echo "Applying $migration..."
+    psql "$DATABASE_URL" -v ON_ERROR_STOP=1 --single-transaction -f "$migration" \
+        -c "INSERT INTO schema_migrations (version) VALUES ('$version')"
+
+    if [ $? -ne 0 ]; then
+        echo "✗ Migration failed: $migration"
+        exit 1
+    fi
+done
+
+echo "✓ Bail application tables are up to date!"
@@ -0,0 +1,410 @@
+use std::sync::Arc;
+
+use axum::{Json, Extension, extract::{State, Path, Query}, http::StatusCode};
+use password_hash::rand_core::{OsRng, RngCore};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    admin::models::{
+        AdminUserView, AdminAction, CreateUserByAdmin, VerificationDecision,
+        UserSearchQuery, ChangeRole, SuspendUser, PasswordResetResponse,
+    },
+    auth::{handlers::{hash_password, send_verification_email}, api_keys, jwt::Claims, refresh, revocation::RevocationStore, throttle},
+    config::Config,
+    mailer::Mailer,
+    models::{UserRole, VerificationStatus},
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 50;
+const MAX_PAGE_SIZE: i64 = 200;
+
+const USER_COLUMNS: &str =
+    "id, username, email, email_verified_at, role, verification_status, bar_enrolment_number, suspended_at, password_reset_required, created_at";
+
+fn ensure_admin(claims: &Claims) -> Result<Uuid, (StatusCode, String)> {
+    if claims.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+    Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))
+}
+
+// Admins must not lock themselves out by accident
+fn ensure_not_self(admin_id: Uuid, user_id: Uuid) -> Result<(), (StatusCode, String)> {
+    if admin_id == user_id {
+        return Err((StatusCode::BAD_REQUEST, "Admins cannot change their own account this way".to_string()));
+    }
+    Ok(())
+}
+
+fn normalize_enrolment_number(number: Option<&str>) -> Option<String> {
+    number
+        .map(str::trim)
+        .filter(|n| !n.is_empty())
+        .map(str::to_string)
+}
+
+async fn record_admin_action(conn: &mut sqlx::PgConnection, admin_id: Uuid, target_user_id: Uuid, action: &str, details: serde_json::Value) -> Result<(), (StatusCode, String)> {
+    sqlx::query(
+        "INSERT INTO admin_actions (id, admin_id, target_user_id, action, details) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(admin_id)
+    .bind(target_user_id)
+    .bind(action)
+    .bind(details)
+    .execute(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record admin action: {}", e)))?;
+
+    Ok(())
+}
+
+
+
+pub async fn search_users( State(db): State<DbPool>, claims: Claims, Query(query): Query<UserSearchQuery>, ) -> Result<Json<Vec<AdminUserView>>, (StatusCode, String)> {
+
+    ensure_admin(&claims)?;
+
+    let users = sqlx::query_as::<_, AdminUserView>(&format!(
+        r#"
+        SELECT {} FROM users
+        WHERE ($1::varchar IS NULL OR username ILIKE '%' || $1 || '%' OR email ILIKE '%' || $1 || '%')
+          AND ($2::varchar IS NULL OR role = $2)
+          AND ($3::varchar IS NULL OR verification_status = $3)
+          AND ($4::boolean IS NULL OR (suspended_at IS NOT NULL) = $4)
+        ORDER BY created_at DESC, id
+        LIMIT $5 OFFSET $6
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(&query.q)
+    .bind(query.role)
+    .bind(query.verification_status)
+    .bind(query.suspended)
+    .bind(query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE))
+    .bind(query.offset.unwrap_or(0).max(0))
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch users: {}", e)))?;
+
+    Ok(Json(users))
+}
+
+
+
+pub async fn get_user( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    ensure_admin(&claims)?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS))
+        .bind(user_id)
+        .fetch_optional(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    Ok(Json(user))
+}
+
+
+
+pub async fn get_user_actions( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<Json<Vec<AdminAction>>, (StatusCode, String)> {
+
+    ensure_admin(&claims)?;
+
+    let actions = sqlx::query_as::<_, AdminAction>(
+        "SELECT * FROM admin_actions WHERE target_user_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch admin actions: {}", e)))?;
+
+    Ok(Json(actions))
+}
+
+
+
+// Judges, clerks and admins can't self-register. Accounts created here start out verified,
+// but the email address still has to be confirmed by its owner
+pub async fn create_user( State(db): State<DbPool>, Extension(config): Extension<Config>, Extension(mailer): Extension<Arc<dyn Mailer>>, claims: Claims, Json(payload): Json<CreateUserByAdmin>, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+
+    let bar_enrolment_number = normalize_enrolment_number(payload.bar_enrolment_number.as_deref());
+    if payload.role == UserRole::Lawyer && bar_enrolment_number.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Lawyers must have a Bar Council enrolment number".to_string()));
+    }
+
+    config.password_policy.check(&payload.password, &payload.username, &payload.email).await?;
+    let password_hash = hash_password(&payload.password)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        r#"
+        INSERT INTO users (id, username, email, password_hash, role, verification_status, bar_enrolment_number)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&payload.username)
+    .bind(&payload.email)
+    .bind(&password_hash)
+    .bind(payload.role)
+    .bind(VerificationStatus::Verified)
+    .bind(&bar_enrolment_number)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to create user: {}", e)))?;
+
+    record_admin_action(&mut tx, admin_id, user.id, "create_user", json!({ "role": payload.role })).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    send_verification_email(&db, &config, mailer, user.id, &user.email).await?;
+
+    Ok(Json(user))
+}
+
+
+
+pub async fn set_verification_status( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, Json(payload): Json<VerificationDecision>, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+
+    if payload.status == VerificationStatus::Pending {
+        return Err((StatusCode::BAD_REQUEST, "Verification decision must be 'verified' or 'rejected'".to_string()));
+    }
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        "UPDATE users SET verification_status = $1 WHERE id = $2 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(payload.status)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update user: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    record_admin_action(&mut tx, admin_id, user_id, "set_verification", json!({ "status": payload.status })).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(user))
+}
+
+
+
+// Suspension takes effect immediately: the token extractor refuses suspended accounts
+pub async fn suspend_user( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, Json(payload): Json<SuspendUser>, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+    ensure_not_self(admin_id, user_id)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        "UPDATE users SET suspended_at = COALESCE(suspended_at, NOW()) WHERE id = $1 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to suspend user: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    refresh::revoke_all_for_user(&mut tx, user_id).await?;
+
+    record_admin_action(&mut tx, admin_id, user_id, "suspend", json!({ "reason": payload.reason })).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(user))
+}
+
+
+
+pub async fn reactivate_user( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        "UPDATE users SET suspended_at = NULL WHERE id = $1 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to reactivate user: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    record_admin_action(&mut tx, admin_id, user_id, "reactivate", json!({})).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(user))
+}
+
+
+
+// Lifts a failed-login lockout on the account before it runs out
+pub async fn unlock_user( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!("SELECT {} FROM users WHERE id = $1", USER_COLUMNS))
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    throttle::clear(&mut *tx, &throttle::account_key(&user.email)).await?;
+    record_admin_action(&mut tx, admin_id, user_id, "unlock", json!({})).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(user))
+}
+
+
+
+// Tokens carry the role, so the extractor rejects any token issued before the change
+pub async fn change_user_role( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, Json(payload): Json<ChangeRole>, ) -> Result<Json<AdminUserView>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+    ensure_not_self(admin_id, user_id)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let previous_role: String = sqlx::query_scalar("SELECT role FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    let bar_enrolment_number = normalize_enrolment_number(payload.bar_enrolment_number.as_deref());
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        r#"
+        UPDATE users
+        SET role = $1, bar_enrolment_number = COALESCE($2, bar_enrolment_number), verification_status = $3
+        WHERE id = $4
+        RETURNING {}
+        "#,
+        USER_COLUMNS
+    ))
+    .bind(payload.role)
+    .bind(&bar_enrolment_number)
+    .bind(VerificationStatus::Verified)
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to change role: {}", e)))?;
+
+    if payload.role == UserRole::Lawyer && user.bar_enrolment_number.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Lawyers must have a Bar Council enrolment number".to_string()));
+    }
+
+    record_admin_action(&mut tx, admin_id, user_id, "change_role", json!({ "from": previous_role, "to": payload.role })).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(user))
+}
+
+
+
+// Replaces the password with a one-off temporary one and signs the user out everywhere;
+// they have to choose a new password at their next login
+pub async fn force_password_reset( State(db): State<DbPool>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<Json<PasswordResetResponse>, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+    ensure_not_self(admin_id, user_id)?;
+
+    let mut secret = [0u8; 12];
+    OsRng.fill_bytes(&mut secret);
+    let temporary_password = hex::encode(secret);
+    let password_hash = hash_password(&temporary_password)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user = sqlx::query_as::<_, AdminUserView>(&format!(
+        "UPDATE users SET password_hash = $1, password_reset_required = TRUE WHERE id = $2 RETURNING {}",
+        USER_COLUMNS
+    ))
+    .bind(&password_hash)
+    .bind(user_id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to reset password: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    refresh::revoke_all_for_user(&mut tx, user_id).await?;
+
+    record_admin_action(&mut tx, admin_id, user_id, "reset_password", json!({})).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(PasswordResetResponse { user, temporary_password }))
+}
+
+
+
+// For compromised accounts: every access and refresh token issued so far stops working
+pub async fn revoke_user_tokens( State(db): State<DbPool>, Extension(revocations): Extension<Arc<RevocationStore>>, Path(user_id): Path<Uuid>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let admin_id = ensure_admin(&claims)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1)")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "User not found".to_string()));
+    }
+
+    revocations.revoke_user(&mut tx, user_id).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    refresh::revoke_all_for_user(&mut tx, user_id).await?;
+    api_keys::revoke_all_for_user(&mut tx, user_id).await?;
+
+    record_admin_action(&mut tx, admin_id, user_id, "revoke_tokens", json!({})).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
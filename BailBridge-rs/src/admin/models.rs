@@ -0,0 +1,75 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::models::{UserRole, VerificationStatus};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminUserView {
+    pub id: Uuid,
+    pub username: String,
+    pub email: String,
+    pub email_verified_at: Option<DateTime<Utc>>,
+    pub role: String,
+    pub verification_status: VerificationStatus,
+    pub bar_enrolment_number: Option<String>,
+    pub suspended_at: Option<DateTime<Utc>>,
+    pub password_reset_required: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateUserByAdmin {
+    pub username: String,
+    pub email: String,
+    pub password: String,
+    pub role: UserRole,
+    #[serde(default)]
+    pub bar_enrolment_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct VerificationDecision {
+    pub status: VerificationStatus, // verified or rejected
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UserSearchQuery {
+    pub q: Option<String>, // matches username or email
+    pub role: Option<UserRole>,
+    pub verification_status: Option<VerificationStatus>,
+    pub suspended: Option<bool>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeRole {
+    pub role: UserRole,
+    #[serde(default)]
+    pub bar_enrolment_number: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuspendUser {
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PasswordResetResponse {
+    #[serde(flatten)]
+    pub user: AdminUserView,
+    // Shown once; the user must replace it at their next login
+    pub temporary_password: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AdminAction {
+    pub id: Uuid,
+    pub admin_id: Uuid,
+    pub target_user_id: Uuid,
+    pub action: String,
+    pub details: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
@@ -0,0 +1,269 @@
+use chrono::{SecondsFormat, SubsecRound};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    audit::models::{AuditEntry, AuditEvent, AuditVerification},
+    db::DbPool,
+};
+
+/// `prev_hash` of the very first entry.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// Advisory lock key serialising appends, so each entry links to the one before it
+const AUDIT_CHAIN_LOCK: i64 = 0x0041_5544_4954; // "AUDIT"
+
+const VERIFY_BATCH_SIZE: i64 = 1000;
+
+#[derive(Serialize)]
+struct HashInput<'a> {
+    prev_hash: &'a str,
+    occurred_at: String,
+    actor_id: Option<Uuid>,
+    actor_role: Option<&'a str>,
+    action: &'a str,
+    application_number: Option<&'a str>,
+    ip_address: Option<&'a str>,
+    user_agent: Option<&'a str>,
+    outcome: i16,
+}
+
+fn entry_hash(prev_hash: &str, event: &AuditEvent) -> String {
+    let input = HashInput {
+        prev_hash,
+        occurred_at: event.occurred_at.to_rfc3339_opts(SecondsFormat::Micros, true),
+        actor_id: event.actor_id,
+        actor_role: event.actor_role.as_deref(),
+        action: &event.action,
+        application_number: event.application_number.as_deref(),
+        ip_address: event.ip_address.as_deref(),
+        user_agent: event.user_agent.as_deref(),
+        outcome: event.outcome,
+    };
+    let canonical = serde_json::to_vec(&input).expect("audit hash input always serializes");
+    hex::encode(Sha256::digest(canonical))
+}
+
+impl From<&AuditEntry> for AuditEvent {
+    fn from(entry: &AuditEntry) -> Self {
+        AuditEvent {
+            occurred_at: entry.occurred_at,
+            actor_id: entry.actor_id,
+            actor_role: entry.actor_role.clone(),
+            action: entry.action.clone(),
+            application_number: entry.application_number.clone(),
+            ip_address: entry.ip_address.clone(),
+            user_agent: entry.user_agent.clone(),
+            outcome: entry.outcome,
+        }
+    }
+}
+
+// An entry holds if it links to the entry before it and its own hash still matches its contents
+fn entry_intact(expected_prev: &str, entry: &AuditEntry) -> bool {
+    entry.prev_hash == expected_prev && entry.hash == entry_hash(&entry.prev_hash, &AuditEvent::from(entry))
+}
+
+// Checks a batch of consecutive entries against the chain so far, advancing `expected_prev`
+// and `entries_checked` past each intact one. Returns the id of the first broken entry.
+fn check_batch(expected_prev: &mut String, entries_checked: &mut i64, batch: &[AuditEntry]) -> Option<i64> {
+    for entry in batch {
+        if !entry_intact(expected_prev, entry) {
+            return Some(entry.id);
+        }
+        *expected_prev = entry.hash.clone();
+        *entries_checked += 1;
+    }
+    None
+}
+
+/// Appends an event to the end of the hash chain. Appends hold one global lock until
+/// they commit, so every audited request in the deployment waits its turn here.
+pub async fn append(db: &DbPool, mut event: AuditEvent) -> Result<(), sqlx::Error> {
+    // Postgres keeps microseconds; hash exactly what will be stored
+    event.occurred_at = event.occurred_at.trunc_subsecs(6);
+
+    let mut tx = db.begin().await?;
+
+    // Serialises every audited request across all instances: each needs the hash of the
+    // entry before it. Keep this transaction to the two statements below.
+    sqlx::query("SELECT pg_advisory_xact_lock($1)")
+        .bind(AUDIT_CHAIN_LOCK)
+        .execute(&mut *tx)
+        .await?;
+
+    let prev_hash: String = sqlx::query_scalar("SELECT hash FROM audit_log ORDER BY id DESC LIMIT 1")
+        .fetch_optional(&mut *tx)
+        .await?
+        .unwrap_or_else(|| GENESIS_HASH.to_string());
+
+    let hash = entry_hash(&prev_hash, &event);
+
+    sqlx::query(
+        r#"
+        INSERT INTO audit_log (occurred_at, actor_id, actor_role, action, application_number, ip_address, user_agent, outcome, prev_hash, hash)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+        "#
+    )
+    .bind(event.occurred_at)
+    .bind(event.actor_id)
+    .bind(&event.actor_role)
+    .bind(&event.action)
+    .bind(&event.application_number)
+    .bind(&event.ip_address)
+    .bind(&event.user_agent)
+    .bind(event.outcome)
+    .bind(&prev_hash)
+    .bind(&hash)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Walks the whole chain and reports the first entry that was edited,
+/// removed from between its neighbours, or inserted out of band.
+pub async fn verify(db: &DbPool) -> Result<AuditVerification, sqlx::Error> {
+    let mut expected_prev = GENESIS_HASH.to_string();
+    let mut last_id = 0_i64;
+    let mut entries_checked = 0_i64;
+
+    loop {
+        let batch = sqlx::query_as::<_, AuditEntry>(
+            "SELECT * FROM audit_log WHERE id > $1 ORDER BY id LIMIT $2"
+        )
+        .bind(last_id)
+        .bind(VERIFY_BATCH_SIZE)
+        .fetch_all(db)
+        .await?;
+
+        let Some(last) = batch.last() else {
+            break;
+        };
+        last_id = last.id;
+
+        if let Some(first_invalid_id) = check_batch(&mut expected_prev, &mut entries_checked, &batch) {
+            return Ok(AuditVerification {
+                valid: false,
+                entries_checked,
+                first_invalid_id: Some(first_invalid_id),
+            });
+        }
+    }
+
+    Ok(AuditVerification {
+        valid: true,
+        entries_checked,
+        first_invalid_id: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+
+    fn event(action: &str) -> AuditEvent {
+        AuditEvent {
+            occurred_at: Utc.with_ymd_and_hms(2026, 2, 20, 10, 0, 0).unwrap(),
+            actor_id: Some(Uuid::nil()),
+            actor_role: Some("judge".to_string()),
+            action: action.to_string(),
+            application_number: Some("BA-2026-0001".to_string()),
+            ip_address: Some("203.0.113.7".to_string()),
+            user_agent: None,
+            outcome: 200,
+        }
+    }
+
+    // Links events the way `append` does
+    fn chain(actions: &[&str]) -> Vec<AuditEntry> {
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let mut entries = Vec::new();
+        for (id, action) in actions.iter().enumerate() {
+            let event = event(action);
+            let hash = entry_hash(&prev_hash, &event);
+            entries.push(AuditEntry {
+                id: id as i64 + 1,
+                occurred_at: event.occurred_at,
+                actor_id: event.actor_id,
+                actor_role: event.actor_role,
+                action: event.action,
+                application_number: event.application_number,
+                ip_address: event.ip_address,
+                user_agent: event.user_agent,
+                outcome: event.outcome,
+                prev_hash: std::mem::replace(&mut prev_hash, hash.clone()),
+                hash,
+            });
+        }
+        entries
+    }
+
+    // Runs `check_batch` over the entries in batches of `size`, as `verify` does
+    fn first_invalid_in_batches(entries: &[AuditEntry], size: usize) -> (Option<i64>, i64) {
+        let mut expected_prev = GENESIS_HASH.to_string();
+        let mut entries_checked = 0;
+        for batch in entries.chunks(size) {
+            if let Some(id) = check_batch(&mut expected_prev, &mut entries_checked, batch) {
+                return (Some(id), entries_checked);
+            }
+        }
+        (None, entries_checked)
+    }
+
+    fn first_invalid(entries: &[AuditEntry]) -> Option<i64> {
+        first_invalid_in_batches(entries, entries.len().max(1)).0
+    }
+
+    #[test]
+    fn hash_covers_every_field_and_the_link() {
+        let base = entry_hash(GENESIS_HASH, &event("GET /cause-list"));
+        assert_eq!(base.len(), 64);
+        assert_eq!(base, entry_hash(GENESIS_HASH, &event("GET /cause-list")));
+
+        assert_ne!(base, entry_hash(&"1".repeat(64), &event("GET /cause-list")));
+        assert_ne!(base, entry_hash(GENESIS_HASH, &event("GET /cause-list/")));
+        assert_ne!(base, entry_hash(GENESIS_HASH, &AuditEvent { outcome: 403, ..event("GET /cause-list") }));
+        assert_ne!(base, entry_hash(GENESIS_HASH, &AuditEvent { actor_role: None, ..event("GET /cause-list") }));
+    }
+
+    #[test]
+    fn untouched_chain_verifies() {
+        assert_eq!(first_invalid(&chain(&["POST /login", "GET /me", "GET /cause-list"])), None);
+    }
+
+    #[test]
+    fn edited_entry_is_caught() {
+        let mut entries = chain(&["POST /login", "GET /me", "GET /cause-list"]);
+        entries[1].outcome = 500;
+        assert_eq!(first_invalid(&entries), Some(2));
+    }
+
+    #[test]
+    fn removed_entry_is_caught() {
+        let mut entries = chain(&["POST /login", "GET /me", "GET /cause-list"]);
+        entries.remove(1);
+        assert_eq!(first_invalid(&entries), Some(3));
+    }
+
+    #[test]
+    fn rehashed_entry_is_caught_by_the_next_link() {
+        let mut entries = chain(&["POST /login", "GET /me", "GET /cause-list"]);
+        entries[1].action = "GET /admin/users".to_string();
+        entries[1].hash = entry_hash(&entries[1].prev_hash, &AuditEvent::from(&entries[1]));
+        assert_eq!(first_invalid(&entries), Some(3));
+    }
+
+    #[test]
+    fn links_are_checked_across_batch_boundaries() {
+        let entries = chain(&["POST /login", "GET /me", "GET /cause-list", "GET /me/sessions"]);
+        assert_eq!(first_invalid_in_batches(&entries, 2), (None, 4));
+
+        let mut entries = entries;
+        entries.remove(2);
+        assert_eq!(first_invalid_in_batches(&entries, 2), (Some(4), 2));
+    }
+}
@@ -0,0 +1,59 @@
+use axum::{Json, extract::{State, Query}, http::StatusCode};
+
+use crate::{
+    db::DbPool,
+    audit::{chain, models::{AuditEntry, AuditQuery, AuditVerification}},
+    auth::jwt::Claims,
+};
+
+const DEFAULT_PAGE_SIZE: i64 = 100;
+const MAX_PAGE_SIZE: i64 = 1000;
+
+
+
+pub async fn query_audit_log( State(db): State<DbPool>, claims: Claims, Query(query): Query<AuditQuery>, ) -> Result<Json<Vec<AuditEntry>>, (StatusCode, String)> {
+
+    if claims.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    let entries = sqlx::query_as::<_, AuditEntry>(
+        r#"
+        SELECT * FROM audit_log
+        WHERE ($1::uuid IS NULL OR actor_id = $1)
+          AND ($2::varchar IS NULL OR application_number = $2)
+          AND ($3::varchar IS NULL OR action ILIKE '%' || $3 || '%')
+          AND ($4::timestamptz IS NULL OR occurred_at >= $4)
+          AND ($5::timestamptz IS NULL OR occurred_at < $5)
+        ORDER BY id DESC
+        LIMIT $6 OFFSET $7
+        "#
+    )
+    .bind(query.actor_id)
+    .bind(&query.application_number)
+    .bind(&query.action)
+    .bind(query.from)
+    .bind(query.to)
+    .bind(query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE))
+    .bind(query.offset.unwrap_or(0).max(0))
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to query audit log: {}", e)))?;
+
+    Ok(Json(entries))
+}
+
+
+
+pub async fn verify_audit_log( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<AuditVerification>, (StatusCode, String)> {
+
+    if claims.role != "admin" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    let verification = chain::verify(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to verify audit log: {}", e)))?;
+
+    Ok(Json(verification))
+}
@@ -0,0 +1,179 @@
+use std::{net::{IpAddr, SocketAddr}, sync::{Arc, OnceLock}};
+
+use axum::{
+    extract::{State, FromRequestParts, ConnectInfo, MatchedPath, RawPathParams},
+    http::{Request, HeaderMap, header, request::Parts},
+    middleware::Next,
+    response::Response,
+    body::Body,
+};
+use chrono::Utc;
+use ipnet::IpNet;
+use uuid::Uuid;
+
+use crate::{
+    audit::{chain, models::AuditEvent},
+    auth::jwt::Claims,
+    config::Config,
+    db::DbPool,
+};
+
+/// The client's address: the peer address of the connection, unless the peer is
+/// one of `trusted_proxies`. Then `X-Forwarded-For` is read from the right, past
+/// our own proxies, to the address the outermost one saw; entries further left
+/// were written by the client and prove nothing.
+pub(crate) fn client_ip(headers: &HeaderMap, connect_info: Option<&ConnectInfo<SocketAddr>>, trusted_proxies: &[IpNet]) -> Option<String> {
+    let peer = connect_info.map(|ConnectInfo(addr)| addr.ip())?;
+    let trusted = |ip: &IpAddr| trusted_proxies.iter().any(|net| net.contains(ip));
+    if !trusted(&peer) {
+        return Some(peer.to_string());
+    }
+
+    let forwarded: Vec<IpAddr> = headers
+        .get_all("X-Forwarded-For")
+        .iter()
+        .filter_map(|h| h.to_str().ok())
+        .flat_map(|h| h.split(','))
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect();
+
+    let client = forwarded
+        .iter()
+        .rev()
+        .find(|ip| !trusted(ip))
+        .or(forwarded.first())
+        .unwrap_or(&peer);
+    Some(client.to_string())
+}
+
+/// Where a request came from, for login history and the like.
+pub struct ClientInfo {
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl<S> FromRequestParts<S> for ClientInfo
+where
+    S: Send + Sync,
+{
+    type Rejection = std::convert::Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let trusted_proxies = parts
+            .extensions
+            .get::<Config>()
+            .map(|config| config.trusted_proxies.as_slice())
+            .unwrap_or_default();
+
+        Ok(ClientInfo {
+            ip_address: client_ip(&parts.headers, parts.extensions.get::<ConnectInfo<SocketAddr>>(), trusted_proxies),
+            user_agent: parts
+                .headers
+                .get(header::USER_AGENT)
+                .and_then(|h| h.to_str().ok())
+                .map(|s| s.to_string()),
+        })
+    }
+}
+
+/// Who made a request, filled in by the `Claims` extractor once the handler has
+/// authenticated the caller, so the audit log never has to authenticate it again.
+#[derive(Clone, Default)]
+pub struct AuditActor(Arc<OnceLock<(Option<Uuid>, String)>>);
+
+impl AuditActor {
+    pub fn record(&self, claims: &Claims) {
+        let _ = self.0.set((Uuid::parse_str(&claims.sub).ok(), claims.role.clone()));
+    }
+}
+
+/// Records every request passing through the router in the audit log,
+/// including ones the handler rejected.
+pub async fn audit_middleware( State(db): State<DbPool>, req: Request<Body>, next: Next, ) -> Response {
+
+    let (mut parts, body) = req.into_parts();
+
+    let actor = AuditActor::default();
+    parts.extensions.insert(actor.clone());
+    let Ok(client) = ClientInfo::from_request_parts(&mut parts, &()).await;
+
+    let path = parts
+        .extensions
+        .get::<MatchedPath>()
+        .map(|p| p.as_str().to_string())
+        .unwrap_or_else(|| parts.uri.path().to_string());
+
+    let application_number = RawPathParams::from_request_parts(&mut parts, &())
+        .await
+        .ok()
+        .and_then(|params| {
+            params
+                .iter()
+                .find(|(key, _)| *key == "application_number")
+                .map(|(_, value)| value.to_string())
+        });
+
+    let event = AuditEvent {
+        occurred_at: Utc::now(),
+        actor_id: None,
+        actor_role: None,
+        action: format!("{} {}", parts.method, path),
+        application_number,
+        ip_address: client.ip_address,
+        user_agent: client.user_agent,
+        outcome: 0,
+    };
+
+    let response = next.run(Request::from_parts(parts, body)).await;
+
+    let (actor_id, actor_role) = match actor.0.get() {
+        Some((id, role)) => (*id, Some(role.clone())),
+        None => (None, None),
+    };
+    let event = AuditEvent {
+        actor_id,
+        actor_role,
+        outcome: response.status().as_u16() as i16,
+        ..event
+    };
+    if let Err(e) = chain::append(&db, event).await {
+        eprintln!("Failed to write audit log entry: {}", e);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(headers: &[&str], peer: &str, trusted_proxies: &[&str]) -> Option<String> {
+        let mut map = HeaderMap::new();
+        for value in headers {
+            map.append("X-Forwarded-For", value.parse().unwrap());
+        }
+        let connect_info = ConnectInfo(SocketAddr::new(peer.parse().unwrap(), 40000));
+        let trusted_proxies: Vec<IpNet> = trusted_proxies.iter().map(|net| net.parse().unwrap()).collect();
+        client_ip(&map, Some(&connect_info), &trusted_proxies)
+    }
+
+    #[test]
+    fn ignores_forwarded_for_from_untrusted_peers() {
+        assert_eq!(ip(&["1.2.3.4"], "203.0.113.9", &[]).as_deref(), Some("203.0.113.9"));
+        assert_eq!(ip(&["1.2.3.4"], "203.0.113.9", &["10.0.0.0/8"]).as_deref(), Some("203.0.113.9"));
+    }
+
+    #[test]
+    fn takes_the_address_our_outermost_proxy_saw() {
+        // The client wrote 1.2.3.4 itself; the load balancer appended the address it connected from
+        assert_eq!(ip(&["1.2.3.4, 198.51.100.7"], "10.0.0.2", &["10.0.0.0/8"]).as_deref(), Some("198.51.100.7"));
+        assert_eq!(ip(&["1.2.3.4", "198.51.100.7, 10.0.0.5"], "10.0.0.2", &["10.0.0.0/8"]).as_deref(), Some("198.51.100.7"));
+        assert_eq!(ip(&["garbage, 198.51.100.7"], "10.0.0.2", &["10.0.0.0/8"]).as_deref(), Some("198.51.100.7"));
+    }
+
+    #[test]
+    fn falls_back_when_every_hop_is_trusted() {
+        assert_eq!(ip(&["10.0.0.7, 10.0.0.5"], "10.0.0.2", &["10.0.0.0/8"]).as_deref(), Some("10.0.0.7"));
+        assert_eq!(ip(&[], "10.0.0.2", &["10.0.0.0/8"]).as_deref(), Some("10.0.0.2"));
+    }
+}
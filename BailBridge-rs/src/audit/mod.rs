@@ -0,0 +1,4 @@
+pub mod models;
+pub mod chain;
+pub mod handlers;
+pub mod middlewares;
@@ -0,0 +1,50 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+/// One request as it will be written to the audit log.
+#[derive(Debug, Clone)]
+pub struct AuditEvent {
+    pub occurred_at: DateTime<Utc>,
+    pub actor_id: Option<Uuid>,
+    pub actor_role: Option<String>,
+    pub action: String,
+    pub application_number: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub outcome: i16,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub occurred_at: DateTime<Utc>,
+    pub actor_id: Option<Uuid>,
+    pub actor_role: Option<String>,
+    pub action: String,
+    pub application_number: Option<String>,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub outcome: i16,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AuditQuery {
+    pub actor_id: Option<Uuid>,
+    pub application_number: Option<String>,
+    pub action: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditVerification {
+    pub valid: bool,
+    pub entries_checked: i64,
+    /// First entry whose hash or link to its predecessor does not match
+    pub first_invalid_id: Option<i64>,
+}
@@ -0,0 +1,235 @@
+use axum::http::{Method, StatusCode};
+use chrono::{DateTime, Utc};
+use password_hash::rand_core::{OsRng, RngCore};
+use uuid::Uuid;
+
+use crate::{
+    auth::{jwt::Claims, tokens::{generate_token, hash_token}},
+    config::Config,
+    db::DbPool,
+};
+
+// Keys look like `bb_<prefix>_<secret>`; the prefix is stored in the clear to find the row
+const KEY_TAG: &str = "bb";
+
+#[derive(sqlx::FromRow)]
+struct KeyGrant {
+    id: Uuid,
+    user_id: Uuid,
+    role: String,
+    read_only: bool,
+    endpoints: Option<Vec<String>>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+/// A fresh key: (prefix, full key to hand out once, hash of its secret).
+pub fn generate_key() -> (String, String, String) {
+    let mut prefix = [0u8; 6];
+    OsRng.fill_bytes(&mut prefix);
+    let prefix = hex::encode(prefix);
+    let secret = generate_token();
+    let key = format!("{}_{}_{}", KEY_TAG, prefix, secret);
+    (prefix, key, hash_token(&secret))
+}
+
+/// Splits a presented key into its public prefix and its secret, if it is shaped like one.
+pub fn split_key(key: &str) -> Option<(&str, &str)> {
+    key.strip_prefix(KEY_TAG)?.strip_prefix('_')?.split_once('_')
+}
+
+/// Checks an endpoint pattern given when creating a key: `GET /cause-list` or `/cause-list`.
+pub fn validate_endpoint(pattern: &str) -> Result<String, String> {
+    let pattern = pattern.split_whitespace().collect::<Vec<_>>();
+    let (method, path) = match pattern.as_slice() {
+        [path] => (None, *path),
+        [method, path] => (Some(method.to_uppercase()), *path),
+        _ => return Err("Endpoints must look like \"GET /bail-applications/all\"".to_string()),
+    };
+
+    if !path.starts_with('/') {
+        return Err(format!("Endpoint path must start with '/': {}", path));
+    }
+    if let Some(method) = &method {
+        if !["GET", "POST", "PUT", "PATCH", "DELETE"].contains(&method.as_str()) {
+            return Err(format!("Unsupported method in endpoint: {}", method));
+        }
+    }
+
+    Ok(match method {
+        Some(method) => format!("{} {}", method, path),
+        None => path.to_string(),
+    })
+}
+
+/// Only lawyers get keys, for their firm's case-management system; staff accounts stay behind a login.
+pub fn check_can_create(role: &str) -> Result<(), (StatusCode, String)> {
+    if role != "lawyer" {
+        return Err((StatusCode::FORBIDDEN, "Only lawyers can create API keys".to_string()));
+    }
+    Ok(())
+}
+
+// Endpoint patterns are route templates, so `/bail-applications/{application_number}` covers every case
+fn endpoint_allowed(endpoints: &[String], method: &Method, route: &str) -> bool {
+    endpoints.iter().any(|pattern| match pattern.split_once(' ') {
+        Some((allowed_method, path)) => allowed_method == method.as_str() && path == route,
+        None => pattern == route,
+    })
+}
+
+// Keys are for reaching case data; the account itself is managed with a login
+fn account_route(route: &str) -> bool {
+    route == "/me" || route.starts_with("/me/") || route.starts_with("/logout")
+}
+
+// Looks a presented key up without touching it, so a call that is then refused leaves no trace
+async fn find_grant(db: &DbPool, key: &str) -> Result<Option<KeyGrant>, (StatusCode, String)> {
+    let Some((prefix, secret)) = split_key(key) else {
+        return Ok(None);
+    };
+
+    sqlx::query_as::<_, KeyGrant>(
+        r#"
+        SELECT k.id, k.user_id, u.role, k.read_only, k.endpoints, k.expires_at
+        FROM api_keys k
+        JOIN users u ON k.user_id = u.id
+        WHERE k.prefix = $1 AND k.secret_hash = $2
+          AND k.revoked_at IS NULL AND (k.expires_at IS NULL OR k.expires_at > NOW())
+        "#
+    )
+    .bind(prefix)
+    .bind(hash_token(secret))
+    .fetch_optional(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check API key: {}", e)))
+}
+
+/// The user id and role behind `key` if it is live, for callers that only need to know who is calling.
+pub async fn live_holder(db: &DbPool, key: &str) -> Result<Option<(Uuid, String)>, (StatusCode, String)> {
+    Ok(find_grant(db, key).await?.map(|grant| (grant.user_id, grant.role)))
+}
+
+/// Resolves an `X-Api-Key` to the key's id and the identity of the user who created it,
+/// if the key is live and allowed to call `method` on `route`.
+pub async fn authenticate(db: &DbPool, config: &Config, key: &str, method: &Method, route: &str) -> Result<(Uuid, Claims), (StatusCode, String)> {
+    let grant = find_grant(db, key)
+        .await?
+        .ok_or_else(|| (StatusCode::UNAUTHORIZED, "Invalid API key".to_string()))?;
+
+    if account_route(route) {
+        return Err((StatusCode::FORBIDDEN, "API keys cannot be used to manage the account".to_string()));
+    }
+    if grant.read_only && !matches!(*method, Method::GET | Method::HEAD) {
+        return Err((StatusCode::FORBIDDEN, "API key is read-only".to_string()));
+    }
+    if let Some(endpoints) = &grant.endpoints {
+        if !endpoint_allowed(endpoints, method, route) {
+            return Err((StatusCode::FORBIDDEN, "API key is not allowed to call this endpoint".to_string()));
+        }
+    }
+
+    // Same identity a login would give; the key stands in for both the token and the session
+    let now = Utc::now();
+    let expires_at = grant.expires_at.unwrap_or(now + chrono::Duration::seconds(config.access_token_ttl_secs));
+    Ok((grant.id, Claims {
+        sub: grant.user_id.to_string(),
+        role: grant.role,
+        exp: expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+        jti: grant.id.to_string(),
+        sid: grant.id.to_string(),
+    }))
+}
+
+/// Records when and from where a key was used, once its request has been let through.
+pub async fn record_use(db: &DbPool, key_id: Uuid, ip_address: Option<&str>) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE api_keys SET last_used_at = NOW(), last_used_ip = $2 WHERE id = $1")
+        .bind(key_id)
+        .bind(ip_address)
+        .execute(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record API key use: {}", e)))?;
+    Ok(())
+}
+
+pub async fn revoke_all_for_user(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to revoke API keys: {}", e)))?;
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_lawyers_can_create_keys() {
+        assert!(check_can_create("lawyer").is_ok());
+        for role in ["admin", "judge", "user", ""] {
+            assert_eq!(check_can_create(role).unwrap_err().0, StatusCode::FORBIDDEN);
+        }
+    }
+
+    #[test]
+    fn generated_keys_split_back_into_their_parts() {
+        let (prefix, key, secret_hash) = generate_key();
+        let (split_prefix, secret) = split_key(&key).unwrap();
+        assert_eq!(split_prefix, prefix);
+        assert_eq!(prefix.len(), 12);
+        assert_eq!(hash_token(secret), secret_hash);
+    }
+
+    #[test]
+    fn split_key_needs_the_tag_and_both_parts() {
+        assert_eq!(split_key("bb_abc_def"), Some(("abc", "def")));
+        assert_eq!(split_key("bb_abc_def_ghi"), Some(("abc", "def_ghi")));
+        assert_eq!(split_key("bb_abc"), None);
+        assert_eq!(split_key("xx_abc_def"), None);
+        assert_eq!(split_key("bbabc_def"), None);
+        assert_eq!(split_key(""), None);
+    }
+
+    #[test]
+    fn validate_endpoint_normalises_patterns() {
+        assert_eq!(validate_endpoint("/cause-list"), Ok("/cause-list".to_string()));
+        assert_eq!(validate_endpoint("get /cause-list"), Ok("GET /cause-list".to_string()));
+        assert_eq!(validate_endpoint("  POST   /bail-applications "), Ok("POST /bail-applications".to_string()));
+    }
+
+    #[test]
+    fn validate_endpoint_rejects_bad_patterns() {
+        assert!(validate_endpoint("").is_err());
+        assert!(validate_endpoint("cause-list").is_err());
+        assert!(validate_endpoint("GET cause-list").is_err());
+        assert!(validate_endpoint("FETCH /cause-list").is_err());
+        assert!(validate_endpoint("GET /cause-list extra").is_err());
+    }
+
+    #[test]
+    fn endpoint_allowed_matches_method_and_route_template() {
+        let endpoints = vec![
+            "GET /bail-applications/my".to_string(),
+            "/bail-applications/{application_number}".to_string(),
+        ];
+        assert!(endpoint_allowed(&endpoints, &Method::GET, "/bail-applications/my"));
+        assert!(!endpoint_allowed(&endpoints, &Method::POST, "/bail-applications/my"));
+        assert!(endpoint_allowed(&endpoints, &Method::GET, "/bail-applications/{application_number}"));
+        assert!(endpoint_allowed(&endpoints, &Method::PUT, "/bail-applications/{application_number}"));
+        assert!(!endpoint_allowed(&endpoints, &Method::GET, "/bail-applications/all"));
+        assert!(!endpoint_allowed(&[], &Method::GET, "/bail-applications/my"));
+    }
+
+    #[test]
+    fn account_routes_are_off_limits() {
+        assert!(account_route("/me"));
+        assert!(account_route("/me/api-keys"));
+        assert!(account_route("/logout"));
+        assert!(account_route("/logout/all"));
+        assert!(!account_route("/media"));
+        assert!(!account_route("/bail-applications/my"));
+    }
+}
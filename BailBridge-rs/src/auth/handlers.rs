@@ -1,17 +1,23 @@
-use axum::{Json, extract::State, http::StatusCode};
-use serde::Serialize;
+use axum::{Json, Extension, extract::{State, Path}, http::StatusCode, response::Redirect};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use sqlx::Row;
-use std::sync::OnceLock;
+use std::sync::{Arc, OnceLock};
 
 use argon2::{Argon2, PasswordHasher, PasswordVerifier, Algorithm, Version, Params};
 use password_hash::{SaltString, PasswordHash, rand_core::OsRng};
 
 use crate::{
     db::DbPool,
-    models::{RegisterUser, LoginUser},
-    auth::jwt::create_jwt,
-    config::Config,
+    models::{
+        RegisterUser, LoginUser, UserRole, VerificationStatus, ForgotPassword, ResetPassword, ChangePassword,
+        ConfirmEmail, ResendVerification, SecondFactor, MfaLogin, MfaSetup, LoginEvent,
+        OidcCallback, OidcLinkStart, Session, CreateApiKey, ApiKey, CreatedApiKey,
+    },
+    audit::middlewares::ClientInfo,
+    mailer::{self, Email, Mailer},
+    auth::{api_keys, sessions, jwt::{create_jwt, Claims}, refresh::{self, RefreshToken}, revocation::RevocationStore, tokens::{generate_token, hash_token}, throttle, totp, oidc::{ExternalIdentity, OidcClient, PendingLogin}},
+    config::{Config, OidcProviderConfig},
 };
 
 // Reusable Argon2 instance - created once and reused
@@ -25,69 +31,1491 @@ fn get_argon2() -> &'static Argon2<'static> {
     })
 }
 
+// Checked against when the email is unknown, so a miss takes as long as a wrong password
+static DUMMY_HASH: OnceLock<String> = OnceLock::new();
+
+fn dummy_hash() -> &'static str {
+    DUMMY_HASH.get_or_init(|| {
+        hash_password("not-a-real-password").expect("Failed to create dummy password hash")
+    })
+}
+
+pub(crate) fn hash_password(password: &str) -> Result<String, (StatusCode, String)> {
+    let salt = SaltString::generate(&mut OsRng);
+    get_argon2()
+        .hash_password(password.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to hash password: {}", e)))
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
     pub token: String,
+    pub refresh_token: String,
+    pub expires_in: i64, // access token lifetime in seconds
     pub role: String,
+    pub verification_status: VerificationStatus,
+    pub email_verified: bool,
 }
 
+#[derive(Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
 
-pub async fn register_user( State((db, config)): State<(DbPool, Config)>, Json(payload): Json<RegisterUser>) -> Result<Json<AuthResponse>, (StatusCode, String)> {
-   
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = get_argon2();
-    
-    let hash_password = argon2
-        .hash_password(payload.password.as_bytes(), &salt)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to hash password: {}", e)))?
-        .to_string();
+/// Returned by the password step when the account has to present a second factor.
+#[derive(Serialize)]
+pub struct MfaChallenge {
+    pub mfa_required: bool,
+    pub challenge_token: String,
+    pub expires_in: i64,
+    // No authenticator yet: set one up with /login/mfa/setup before answering the challenge
+    pub enrolment_required: bool,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum LoginResponse {
+    Session(AuthResponse),
+    Challenge(MfaChallenge),
+}
+
+#[derive(Serialize)]
+pub struct MfaLoginResponse {
+    #[serde(flatten)]
+    pub session: AuthResponse,
+    // Only on the login that completes enrolment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recovery_codes: Option<Vec<String>>,
+}
+
+#[derive(Serialize)]
+pub struct TotpEnrolment {
+    pub secret: String,
+    pub provisioning_uri: String, // render as a QR code
+}
+
+#[derive(Serialize)]
+pub struct RecoveryCodes {
+    pub recovery_codes: Vec<String>,
+}
+
+const MAX_CHALLENGE_ATTEMPTS: i32 = 5;
+const OIDC_LOGIN_TTL_SECS: i64 = 10 * 60;
+
+#[derive(sqlx::FromRow)]
+struct LoginChallenge {
+    id: Uuid,
+    user_id: Uuid,
+    attempts: i32,
+    new_password_hash: Option<String>,
+}
+
+#[derive(sqlx::FromRow)]
+struct LoginAccount {
+    id: Uuid,
+    email: String,
+    role: String,
+    verification_status: VerificationStatus,
+    email_verified: bool,
+    suspended: bool,
+    password_reset_required: bool,
+    totp_enabled: bool,
+}
+
+#[derive(sqlx::FromRow)]
+struct TotpState {
+    email: String,
+    role: String,
+    verification_status: VerificationStatus,
+    email_verified: bool,
+    totp_secret: Option<String>,
+    totp_enabled: bool,
+    totp_last_used_step: Option<i64>,
+}
+
+async fn fetch_totp_state(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<TotpState, (StatusCode, String)> {
+    sqlx::query_as::<_, TotpState>(
+        r#"
+        SELECT email, role, verification_status, email_verified_at IS NOT NULL AS email_verified,
+               totp_secret, totp_enabled_at IS NOT NULL AS totp_enabled, totp_last_used_step
+        FROM users WHERE id = $1 FOR UPDATE
+        "#
+    )
+    .bind(user_id)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch account: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))
+}
+
+async fn fetch_challenge(conn: &mut sqlx::PgConnection, challenge_token: &str) -> Result<LoginChallenge, (StatusCode, String)> {
+    let challenge = sqlx::query_as::<_, LoginChallenge>(
+        "SELECT id, user_id, attempts, new_password_hash FROM login_challenges WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW() FOR UPDATE"
+    )
+    .bind(hash_token(challenge_token))
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch login challenge: {}", e)))?
+    .ok_or((StatusCode::UNAUTHORIZED, "Invalid or expired login challenge".to_string()))?;
+
+    if challenge.attempts >= MAX_CHALLENGE_ATTEMPTS {
+        return Err((StatusCode::UNAUTHORIZED, "Too many attempts, please log in again".to_string()));
+    }
+    Ok(challenge)
+}
+
+/// Checks a TOTP code (or, once enrolled, a recovery code) and burns what was used.
+async fn check_second_factor(conn: &mut sqlx::PgConnection, user_id: Uuid, state: &TotpState, factor: &SecondFactor) -> Result<bool, (StatusCode, String)> {
+    if let Some(code) = &factor.code {
+        let secret = state.totp_secret
+            .as_deref()
+            .ok_or((StatusCode::BAD_REQUEST, "No authenticator has been set up".to_string()))?;
+
+        let Some(step) = totp::verify(secret, code, state.totp_last_used_step)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?
+        else {
+            return Ok(false);
+        };
+
+        sqlx::query("UPDATE users SET totp_last_used_step = $1 WHERE id = $2")
+            .bind(step)
+            .bind(user_id)
+            .execute(conn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record TOTP use: {}", e)))?;
+        return Ok(true);
+    }
+
+    if let Some(recovery_code) = factor.recovery_code.as_deref().filter(|_| state.totp_enabled) {
+        let used = sqlx::query(
+            "UPDATE totp_recovery_codes SET used_at = NOW() WHERE user_id = $1 AND code_hash = $2 AND used_at IS NULL"
+        )
+        .bind(user_id)
+        .bind(hash_token(&totp::normalize_recovery_code(recovery_code)))
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check recovery code: {}", e)))?
+        .rows_affected();
+        return Ok(used > 0);
+    }
+
+    Err((StatusCode::BAD_REQUEST, "Provide a code from your authenticator app or a recovery code".to_string()))
+}
+
+// Replaces any earlier set; the plain codes are returned once and never stored
+async fn replace_recovery_codes(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<Vec<String>, (StatusCode, String)> {
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to clear recovery codes: {}", e)))?;
+
+    let codes = totp::generate_recovery_codes();
+    for code in &codes {
+        sqlx::query("INSERT INTO totp_recovery_codes (id, user_id, code_hash) VALUES ($1, $2, $3)")
+            .bind(Uuid::new_v4())
+            .bind(user_id)
+            .bind(hash_token(code))
+            .execute(&mut *conn)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to store recovery codes: {}", e)))?;
+    }
+    Ok(codes)
+}
+
+async fn enable_totp(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<Vec<String>, (StatusCode, String)> {
+    sqlx::query("UPDATE users SET totp_enabled_at = NOW() WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to enable two-factor authentication: {}", e)))?;
+
+    replace_recovery_codes(conn, user_id).await
+}
+
+async fn begin_totp_enrolment(conn: &mut sqlx::PgConnection, config: &Config, user_id: Uuid, state: &TotpState) -> Result<TotpEnrolment, (StatusCode, String)> {
+    if state.totp_enabled {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already set up".to_string()));
+    }
+
+    let secret = totp::new_secret();
+    let provisioning_uri = totp::provisioning_uri(&secret, &config.totp_issuer, &state.email)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    sqlx::query("UPDATE users SET totp_secret = $1, totp_last_used_step = NULL WHERE id = $2")
+        .bind(&secret)
+        .bind(user_id)
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to store TOTP secret: {}", e)))?;
+
+    Ok(TotpEnrolment { secret, provisioning_uri })
+}
+
+// Every failed login gets the same answer, whether the email or the password was wrong
+fn invalid_credentials() -> (StatusCode, String) {
+    (StatusCode::UNAUTHORIZED, "Invalid email or password".to_string())
+}
+
+fn throttle_keys(email: &str, client: &ClientInfo) -> Vec<String> {
+    let mut keys = vec![throttle::account_key(email)];
+    if let Some(ip_address) = &client.ip_address {
+        keys.push(throttle::ip_key(ip_address));
+    }
+    keys
+}
+
+async fn record_login(db: &DbPool, user_id: Option<Uuid>, email: &str, client: &ClientInfo, failure_reason: Option<&str>) -> Result<(), (StatusCode, String)> {
+    sqlx::query(
+        "INSERT INTO login_events (id, user_id, email, ip_address, user_agent, success, failure_reason) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(email)
+    .bind(&client.ip_address)
+    .bind(&client.user_agent)
+    .bind(failure_reason.is_none())
+    .bind(failure_reason)
+    .execute(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record login: {}", e)))?;
+
+    Ok(())
+}
+
+// Counts a failed attempt against both the account and the client address
+async fn record_failed_login(db: &DbPool, config: &Config, user_id: Option<Uuid>, email: &str, client: &ClientInfo, reason: &str) -> Result<(), (StatusCode, String)> {
+    throttle::record_failure(db, config, &throttle::account_key(email), config.login_max_failures_per_account).await?;
+    if let Some(ip_address) = &client.ip_address {
+        throttle::record_failure(db, config, &throttle::ip_key(ip_address), config.login_max_failures_per_ip).await?;
+    }
+    record_login(db, user_id, email, client, Some(reason)).await
+}
+
+async fn record_successful_login(db: &DbPool, user_id: Uuid, email: &str, client: &ClientInfo) -> Result<(), (StatusCode, String)> {
+    // Only the account counter: logging into one's own account must not clear an address's record
+    throttle::clear(db, &throttle::account_key(email)).await?;
+    record_login(db, Some(user_id), email, client, None).await
+}
+
+fn access_token(user_id: Uuid, role: &str, family_id: Uuid, config: &Config) -> Result<String, (StatusCode, String)> {
+    create_jwt(&user_id.to_string(), role, &family_id.to_string(), &config.jwt_keys, config.access_token_ttl_secs)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create JWT: {}", e)))
+}
+
+// A login starts a new refresh token family, tracked as a session
+#[allow(clippy::too_many_arguments)]
+async fn start_session(db: &DbPool, config: &Config, mailer: &Arc<dyn Mailer>, client: &ClientInfo, user_id: Uuid, role: String, verification_status: VerificationStatus, email_verified: bool) -> Result<AuthResponse, (StatusCode, String)> {
+    let family_id = Uuid::new_v4();
+    let token = access_token(user_id, &role, family_id, config)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+    let new_device = sessions::start(&mut tx, family_id, user_id, client).await?;
+    let (_, refresh_token) = refresh::issue(&mut tx, user_id, family_id, config.refresh_token_ttl_secs).await?;
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    // Lawyers and judges hold case data, so a login they don't recognise should not go unnoticed
+    if new_device && (role == "lawyer" || role == "judge") {
+        notify_new_device(db, mailer.clone(), user_id, client).await?;
+    }
+
+    Ok(AuthResponse { token, refresh_token, expires_in: config.access_token_ttl_secs, role, verification_status, email_verified })
+}
+
+async fn notify_new_device(db: &DbPool, mailer: Arc<dyn Mailer>, user_id: Uuid, client: &ClientInfo) -> Result<(), (StatusCode, String)> {
+    let email: String = sqlx::query_scalar("SELECT email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+
+    mailer::send_in_background(mailer, Email {
+        to: email,
+        subject: "New sign-in to your BailBridge account".to_string(),
+        body: format!(
+            "Your BailBridge account was just signed in to from a new device.\n\n\
+             Time: {}\nDevice: {}\nIP address: {}\n\n\
+             If this was you, there is nothing to do. If not, sign that session out\n\
+             from your account's session list and change your password.",
+            chrono::Utc::now().format("%Y-%m-%d %H:%M UTC"),
+            client.user_agent.as_deref().unwrap_or("unknown"),
+            client.ip_address.as_deref().unwrap_or("unknown"),
+        ),
+    });
+
+    Ok(())
+}
+
+// Last step of every login once the first factor checks out: a session, or a
+// challenge for the second factor when the account has or needs one. The new
+// password of a forced reset only takes effect once the whole login succeeds.
+async fn complete_login(db: &DbPool, config: &Config, mailer: &Arc<dyn Mailer>, client: &ClientInfo, account: LoginAccount, new_password_hash: Option<String>) -> Result<LoginResponse, (StatusCode, String)> {
+    if account.totp_enabled || config.mfa_required_roles.contains(&account.role) {
+        let challenge_token = generate_token();
+
+        sqlx::query("INSERT INTO login_challenges (id, user_id, token_hash, expires_at, new_password_hash) VALUES ($1, $2, $3, $4, $5)")
+            .bind(Uuid::new_v4())
+            .bind(account.id)
+            .bind(hash_token(&challenge_token))
+            .bind(chrono::Utc::now() + chrono::Duration::seconds(config.mfa_challenge_ttl_secs))
+            .bind(&new_password_hash)
+            .execute(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create login challenge: {}", e)))?;
+
+        return Ok(LoginResponse::Challenge(MfaChallenge {
+            mfa_required: true,
+            challenge_token,
+            expires_in: config.mfa_challenge_ttl_secs,
+            enrolment_required: !account.totp_enabled,
+        }));
+    }
+
+    if let Some(password_hash) = &new_password_hash {
+        set_reset_password(db, account.id, password_hash).await?;
+    }
+
+    record_successful_login(db, account.id, &account.email, client).await?;
+
+    Ok(LoginResponse::Session(start_session(db, config, mailer, client, account.id, account.role, account.verification_status, account.email_verified).await?))
+}
+
+async fn set_reset_password<'e>(executor: impl sqlx::PgExecutor<'e>, user_id: Uuid, password_hash: &str) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE users SET password_hash = $1, password_reset_required = FALSE WHERE id = $2")
+        .bind(password_hash)
+        .bind(user_id)
+        .execute(executor)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update password: {}", e)))?;
+    Ok(())
+}
+
+fn oidc_provider<'a>(config: &'a Config, name: &str) -> Result<&'a OidcProviderConfig, (StatusCode, String)> {
+    config.oidc_providers
+        .iter()
+        .find(|provider| provider.name == name)
+        .ok_or((StatusCode::NOT_FOUND, format!("Unknown identity provider: {}", name)))
+}
+
+// Only plain user accounts are created from a provider login. Lawyers need a Bar Council
+// enrolment number and admin approval, and judges, clerks and admins are admin-created,
+// so those accounts must exist before a provider identity can be linked to them.
+async fn create_external_user(conn: &mut sqlx::PgConnection, identity: &ExternalIdentity, email: &str, role: UserRole) -> Result<Uuid, (StatusCode, String)> {
+    if role != UserRole::User {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Accounts with the {} role are not created from a provider login; ask an admin to create yours, then link it from your profile", role),
+        ));
+    }
+
+    let base = identity.preferred_username
+        .clone()
+        .unwrap_or_else(|| email.split('@').next().unwrap_or(email).to_string());
+    let taken: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE username = $1)")
+        .bind(&base)
+        .fetch_one(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check username: {}", e)))?;
+    let username = if taken { format!("{}-{}", base, &generate_token()[..6]) } else { base };
+
+    // No usable password until the user sets one through /password/forgot
+    let password_hash = hash_password(&generate_token())?;
+    let user_id = Uuid::new_v4();
+
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, role, verification_status, email_verified_at) VALUES ($1, $2, $3, $4, $5, $6, NOW())"
+    )
+    .bind(user_id)
+    .bind(&username)
+    .bind(email)
+    .bind(&password_hash)
+    .bind(role)
+    .bind(VerificationStatus::Verified)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to create user: {}", e)))?;
+
+    Ok(user_id)
+}
+
+// Finds the user behind an external identity, creating the account on first login.
+// An identity is only linked to an existing account when that account's owner asked
+// for it (`link_user_id`): a matching email alone would let whoever controls the
+// provider take the account over. The provider's role only applies to accounts it
+// creates; existing ones keep the role and status an admin gave them.
+async fn link_external_identity(db: &DbPool, provider: &OidcProviderConfig, identity: &ExternalIdentity, role: UserRole, link_user_id: Option<Uuid>) -> Result<LoginAccount, (StatusCode, String)> {
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let linked: Option<Uuid> = sqlx::query_scalar("SELECT user_id FROM external_identities WHERE provider = $1 AND subject = $2")
+        .bind(&provider.name)
+        .bind(&identity.subject)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch external identity: {}", e)))?;
+
+    let user_id = match (linked, link_user_id) {
+        (Some(user_id), Some(link_user_id)) if user_id != link_user_id => {
+            return Err((StatusCode::CONFLICT, format!("This {} account is already linked to another user", provider.name)));
+        }
+        (Some(user_id), _) => user_id,
+        (None, _) => {
+            let user_id = match link_user_id {
+                Some(user_id) => user_id,
+                None => {
+                    let email = identity.email
+                        .as_deref()
+                        .filter(|_| identity.email_verified)
+                        .ok_or((StatusCode::FORBIDDEN, "The identity provider has not verified an email address for this account".to_string()))?;
+
+                    let existing: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM users WHERE LOWER(email) = $1)")
+                        .bind(email)
+                        .fetch_one(&mut *tx)
+                        .await
+                        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+                    if existing {
+                        return Err((
+                            StatusCode::CONFLICT,
+                            format!("An account with this email address already exists; log in with your password and link your {} account from your profile", provider.name),
+                        ));
+                    }
+
+                    create_external_user(&mut tx, identity, email, role).await?
+                }
+            };
+
+            sqlx::query("INSERT INTO external_identities (id, user_id, provider, subject, email) VALUES ($1, $2, $3, $4, $5)")
+                .bind(Uuid::new_v4())
+                .bind(user_id)
+                .bind(&provider.name)
+                .bind(&identity.subject)
+                .bind(&identity.email)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to link external identity: {}", e)))?;
+
+            user_id
+        }
+    };
+
+    sqlx::query("UPDATE external_identities SET last_login_at = NOW(), email = COALESCE($1, email) WHERE provider = $2 AND subject = $3")
+        .bind(&identity.email)
+        .bind(&provider.name)
+        .bind(&identity.subject)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update external identity: {}", e)))?;
+
+    let account = sqlx::query_as::<_, LoginAccount>(
+        "SELECT id, email, role, verification_status, email_verified_at IS NOT NULL AS email_verified, suspended_at IS NOT NULL AS suspended, password_reset_required, totp_enabled_at IS NOT NULL AS totp_enabled FROM users WHERE id = $1"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+
+    // Group membership at the provider must not promote or demote an account behind an admin's back
+    if account.role != role.as_str() {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("Your {} account maps to the {} role but this account is a {}; ask an admin to review it", provider.name, role, account.role),
+        ));
+    }
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(account)
+}
+
+/// Creates a single-use confirmation link for `email` and mails it.
+pub(crate) async fn send_verification_email(db: &DbPool, config: &Config, mailer: Arc<dyn Mailer>, user_id: Uuid, email: &str) -> Result<(), (StatusCode, String)> {
+    let token = generate_token();
+
+    sqlx::query(
+        "INSERT INTO email_verification_tokens (id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, $4)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(hash_token(&token))
+    .bind(chrono::Utc::now() + chrono::Duration::seconds(config.email_verification_ttl_secs))
+    .execute(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create verification token: {}", e)))?;
+
+    mailer::send_in_background(mailer, Email {
+        to: email.to_string(),
+        subject: "Confirm your BailBridge email address".to_string(),
+        body: format!(
+            "Open this link within {} hours to confirm the email address of your BailBridge account:\n\
+             {}/verify-email?token={}\n\n\
+             If you didn't create an account, ignore this message.",
+            config.email_verification_ttl_secs / 3600, config.app_base_url, token
+        ),
+    });
+
+    Ok(())
+}
+
+
+pub async fn register_user( State((db, config)): State<(DbPool, Config)>, Extension(mailer): Extension<Arc<dyn Mailer>>, client: ClientInfo, Json(payload): Json<RegisterUser>) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+
+    // Only applicants and lawyers may sign up; lawyers wait for an admin to check their enrolment
+    let (verification_status, bar_enrolment_number) = match payload.role {
+        UserRole::User => (VerificationStatus::Verified, None),
+        UserRole::Lawyer => {
+            let enrolment = payload.bar_enrolment_number
+                .as_deref()
+                .map(str::trim)
+                .filter(|n| !n.is_empty())
+                .ok_or((StatusCode::BAD_REQUEST, "Lawyers must provide a Bar Council enrolment number".to_string()))?;
+            (VerificationStatus::Pending, Some(enrolment.to_string()))
+        }
+        role => {
+            return Err((StatusCode::FORBIDDEN, format!("Accounts with role '{}' are created by an admin", role)));
+        }
+    };
+
+    config.password_policy.check(&payload.password, &payload.username, &payload.email).await?;
+    let hash_password = hash_password(&payload.password)?;
 
     let user_id = Uuid::new_v4();
-    let role_str = format!("{:?}", payload.role).to_lowercase();
+    let role_str = payload.role.as_str().to_string();
 
-    sqlx::query("INSERT INTO users (id, username, email, password_hash, role) VALUES ($1, $2, $3, $4, $5)")
+    sqlx::query(
+        "INSERT INTO users (id, username, email, password_hash, role, verification_status, bar_enrolment_number) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
         .bind(user_id)
         .bind(&payload.username)
         .bind(&payload.email)
         .bind(&hash_password)
         .bind(&role_str)
+        .bind(verification_status)
+        .bind(&bar_enrolment_number)
         .execute(&db)
         .await
         .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to create user: {}", e)))?;
 
-    let token = create_jwt(&user_id.to_string(), &role_str, &config.jwt_secret)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create JWT: {}", e)))?;
-    Ok(Json(AuthResponse { token, role: role_str }))
+    send_verification_email(&db, &config, mailer.clone(), user_id, &payload.email).await?;
+
+    Ok(Json(start_session(&db, &config, &mailer, &client, user_id, role_str, verification_status, false).await?))
 }
 
 
 
-pub async fn login_user( State((db, config)): State<(DbPool, Config)>, Json(payload): Json<LoginUser>) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+pub async fn login_user( State((db, config)): State<(DbPool, Config)>, Extension(mailer): Extension<Arc<dyn Mailer>>, client: ClientInfo, Json(payload): Json<LoginUser>) -> Result<Json<LoginResponse>, (StatusCode, String)> {
 
-    let row = sqlx::query("SELECT id, password_hash, role FROM users WHERE email = $1")
+    let row = sqlx::query("SELECT id, username, password_hash, role, verification_status, email_verified_at IS NOT NULL AS email_verified, suspended_at IS NOT NULL AS suspended, password_reset_required, totp_enabled_at IS NOT NULL AS totp_enabled FROM users WHERE email = $1")
         .bind(&payload.email)
-        .fetch_one(&db)
+        .fetch_optional(&db)
         .await
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "User not found".to_string()))?;
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
 
-    let password_hash: String = row.try_get("password_hash")
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get password_hash: {}", e)))?;
+    let id: Option<Uuid> = row.as_ref()
+        .map(|row| row.try_get("id"))
+        .transpose()
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get id: {}", e)))?;
 
-    let role: String = row.try_get("role")
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get role: {}", e)))?;
+    if let Err(locked) = throttle::check(&db, &throttle_keys(&payload.email, &client)).await {
+        record_login(&db, id, &payload.email, &client, Some("locked_out")).await?;
+        return Err(locked);
+    }
 
-    let id: Uuid = row.try_get("id")
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get id: {}", e)))?;
+    let password_hash: String = match &row {
+        Some(row) => row.try_get("password_hash")
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get password_hash: {}", e)))?,
+        None => dummy_hash().to_string(),
+    };
 
     let parsed_hash = PasswordHash::new(&password_hash)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse password hash: {}", e)))?;
 
     // Use shared Argon2 instance for verification
-    get_argon2()
+    let password_ok = get_argon2()
         .verify_password(payload.password.as_bytes(), &parsed_hash)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid password".to_string()))?;
+        .is_ok();
+
+    let (Some(row), Some(id), true) = (row, id, password_ok) else {
+        record_failed_login(&db, &config, id, &payload.email, &client, "invalid_credentials").await?;
+        return Err(invalid_credentials());
+    };
+
+    let role: String = row.try_get("role")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get role: {}", e)))?;
+
+    let verification_status: VerificationStatus = row.try_get("verification_status")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get verification_status: {}", e)))?;
+    let email_verified: bool = row.try_get("email_verified")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get email_verified: {}", e)))?;
+
+    let suspended: bool = row.try_get("suspended")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get suspended: {}", e)))?;
+    if suspended {
+        record_login(&db, Some(id), &payload.email, &client, Some("suspended")).await?;
+        return Err((StatusCode::FORBIDDEN, "Account is suspended".to_string()));
+    }
+
+    // After an admin-forced reset the temporary password only buys a password change
+    let password_reset_required: bool = row.try_get("password_reset_required")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get password_reset_required: {}", e)))?;
+    let new_password_hash = if password_reset_required {
+        let new_password = payload.new_password
+            .as_deref()
+            .filter(|p| !p.is_empty() && *p != payload.password)
+            .ok_or((StatusCode::FORBIDDEN, "Password reset required: log in again with a new_password".to_string()))?;
+        let username: String = row.try_get("username")
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get username: {}", e)))?;
+        config.password_policy.check(new_password, &username, &payload.email).await?;
+        Some(hash_password(new_password)?)
+    } else {
+        None
+    };
+
+    let totp_enabled: bool = row.try_get("totp_enabled")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get totp_enabled: {}", e)))?;
+
+    let account = LoginAccount {
+        id,
+        email: payload.email,
+        role,
+        verification_status,
+        email_verified,
+        suspended,
+        password_reset_required,
+        totp_enabled,
+    };
+
+    Ok(Json(complete_login(&db, &config, &mailer, &client, account, new_password_hash).await?))
+}
+
+
+
+// Second login step: redeems the challenge from /login with a TOTP or recovery code
+pub async fn verify_login_challenge( State((db, config)): State<(DbPool, Config)>, Extension(mailer): Extension<Arc<dyn Mailer>>, client: ClientInfo, Json(payload): Json<MfaLogin>) -> Result<Json<MfaLoginResponse>, (StatusCode, String)> {
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let challenge = fetch_challenge(&mut tx, &payload.challenge_token).await?;
+    let state = fetch_totp_state(&mut tx, challenge.user_id).await?;
+
+    // A lockout reached by guessing codes holds for the rest of the login as well
+    throttle::check(&db, &throttle_keys(&state.email, &client)).await?;
+
+    if !check_second_factor(&mut tx, challenge.user_id, &state, &payload.factor).await? {
+        sqlx::query("UPDATE login_challenges SET attempts = attempts + 1 WHERE id = $1")
+            .bind(challenge.id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record attempt: {}", e)))?;
+        tx.commit().await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+        record_failed_login(&db, &config, Some(challenge.user_id), &state.email, &client, "invalid_second_factor").await?;
+        return Err((StatusCode::UNAUTHORIZED, "Invalid verification code".to_string()));
+    }
+
+    // The first good code after /login/mfa/setup completes enrolment
+    let recovery_codes = if state.totp_enabled {
+        None
+    } else {
+        Some(enable_totp(&mut tx, challenge.user_id).await?)
+    };
+
+    if let Some(password_hash) = &challenge.new_password_hash {
+        set_reset_password(&mut *tx, challenge.user_id, password_hash).await?;
+    }
+
+    sqlx::query("UPDATE login_challenges SET used_at = NOW() WHERE id = $1")
+        .bind(challenge.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to close login challenge: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    record_successful_login(&db, challenge.user_id, &state.email, &client).await?;
+
+    let session = start_session(&db, &config, &mailer, &client, challenge.user_id, state.role, state.verification_status, state.email_verified).await?;
+
+    Ok(Json(MfaLoginResponse { session, recovery_codes }))
+}
+
+
+
+// Lets accounts that must use 2FA set up an authenticator in the middle of logging in
+pub async fn setup_login_totp( State((db, config)): State<(DbPool, Config)>, Json(payload): Json<MfaSetup>) -> Result<Json<TotpEnrolment>, (StatusCode, String)> {
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let challenge = fetch_challenge(&mut tx, &payload.challenge_token).await?;
+    let state = fetch_totp_state(&mut tx, challenge.user_id).await?;
+    let enrolment = begin_totp_enrolment(&mut tx, &config, challenge.user_id, &state).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(enrolment))
+}
+
+
+
+// Exchanges a refresh token for a new access token and a new refresh token.
+// Each refresh token works once; presenting a rotated-out one means it leaked,
+// so the whole family descended from that login is revoked.
+pub async fn refresh_access_token( State((db, config)): State<(DbPool, Config)>, client: ClientInfo, Json(payload): Json<RefreshRequest>) -> Result<Json<AuthResponse>, (StatusCode, String)> {
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let presented = sqlx::query_as::<_, RefreshToken>(
+        "SELECT id, user_id, family_id, expires_at, rotated_at, revoked_at FROM refresh_tokens WHERE token_hash = $1 FOR UPDATE"
+    )
+    .bind(hash_token(&payload.refresh_token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch refresh token: {}", e)))?
+    .ok_or((StatusCode::UNAUTHORIZED, "Invalid refresh token".to_string()))?;
+
+    if presented.revoked_at.is_some() {
+        return Err((StatusCode::UNAUTHORIZED, "Refresh token has been revoked".to_string()));
+    }
+
+    if presented.rotated_at.is_some() {
+        refresh::revoke_family(&mut tx, presented.family_id).await?;
+        tx.commit().await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+        eprintln!("Refresh token reuse detected for user {}, family {} revoked", presented.user_id, presented.family_id);
+        return Err((StatusCode::UNAUTHORIZED, "Refresh token reuse detected; please log in again".to_string()));
+    }
+
+    if presented.expires_at <= chrono::Utc::now() {
+        return Err((StatusCode::UNAUTHORIZED, "Refresh token has expired".to_string()));
+    }
+
+    // Re-read the account so suspensions and role changes apply at refresh time
+    let row = sqlx::query("SELECT role, verification_status, email_verified_at IS NOT NULL AS email_verified, suspended_at IS NOT NULL AS suspended, password_reset_required, totp_enabled_at IS NOT NULL AS totp_enabled FROM users WHERE id = $1")
+        .bind(presented.user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch account: {}", e)))?;
+
+    let role: String = row.try_get("role")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get role: {}", e)))?;
+    let verification_status: VerificationStatus = row.try_get("verification_status")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get verification_status: {}", e)))?;
+    let email_verified: bool = row.try_get("email_verified")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get email_verified: {}", e)))?;
+    let suspended: bool = row.try_get("suspended")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get suspended: {}", e)))?;
+    let password_reset_required: bool = row.try_get("password_reset_required")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get password_reset_required: {}", e)))?;
+
+    if suspended {
+        return Err((StatusCode::FORBIDDEN, "Account is suspended".to_string()));
+    }
+    if password_reset_required {
+        return Err((StatusCode::UNAUTHORIZED, "Password reset required, please log in again".to_string()));
+    }
+
+    let totp_enabled: bool = row.try_get("totp_enabled")
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to get totp_enabled: {}", e)))?;
+    if config.mfa_required_roles.contains(&role) && !totp_enabled {
+        return Err((StatusCode::UNAUTHORIZED, "Two-factor authentication required, please log in again".to_string()));
+    }
+
+    let (new_id, refresh_token) = refresh::issue(&mut tx, presented.user_id, presented.family_id, config.refresh_token_ttl_secs).await?;
+
+    sqlx::query("UPDATE refresh_tokens SET rotated_at = NOW(), replaced_by = $1 WHERE id = $2")
+        .bind(new_id)
+        .bind(presented.id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to rotate refresh token: {}", e)))?;
+
+    sessions::resume(&mut tx, presented.family_id, presented.user_id, &client).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    let token = access_token(presented.user_id, &role, presented.family_id, &config)?;
+
+    Ok(Json(AuthResponse { token, refresh_token, expires_in: config.access_token_ttl_secs, role, verification_status, email_verified }))
+}
+
+
+
+// Ends the current session: this access token and the refresh tokens issued alongside it
+pub async fn logout( State(db): State<DbPool>, Extension(revocations): Extension<Arc<RevocationStore>>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let family_id = Uuid::parse_str(&claims.sid)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid session ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    revocations.revoke_token(&mut tx, &claims).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    refresh::revoke_family(&mut tx, family_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+pub async fn logout_all( State(db): State<DbPool>, Extension(revocations): Extension<Arc<RevocationStore>>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    revocations.revoke_user(&mut tx, user_id).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    refresh::revoke_all_for_user(&mut tx, user_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+// Always answers the same way, whether or not the address belongs to an account
+pub async fn forgot_password( State((db, config)): State<(DbPool, Config)>, Extension(mailer): Extension<Arc<dyn Mailer>>, Json(payload): Json<ForgotPassword>) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id: Option<Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE email = $1 AND suspended_at IS NULL")
+        .bind(payload.email.trim())
+        .fetch_optional(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+
+    if let Some(user_id) = user_id {
+        let token = generate_token();
+
+        sqlx::query(
+            "INSERT INTO password_reset_tokens (id, user_id, token_hash, expires_at) VALUES ($1, $2, $3, $4)"
+        )
+        .bind(Uuid::new_v4())
+        .bind(user_id)
+        .bind(hash_token(&token))
+        .bind(chrono::Utc::now() + chrono::Duration::seconds(config.password_reset_ttl_secs))
+        .execute(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create reset token: {}", e)))?;
+
+        mailer::send_in_background(mailer, Email {
+            to: payload.email.trim().to_string(),
+            subject: "Reset your BailBridge password".to_string(),
+            body: format!(
+                "Someone asked to reset the password of your BailBridge account.\n\n\
+                 Open this link within {} minutes to choose a new one:\n{}/reset-password?token={}\n\n\
+                 If it wasn't you, ignore this message; your password stays the same.",
+                config.password_reset_ttl_secs / 60, config.app_base_url, token
+            ),
+        });
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+
+
+// Sets a new password from an emailed reset token and signs the user out everywhere
+pub async fn reset_password( State((db, config)): State<(DbPool, Config)>, Extension(revocations): Extension<Arc<RevocationStore>>, Json(payload): Json<ResetPassword>) -> Result<StatusCode, (StatusCode, String)> {
+
+    if payload.new_password.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "New password must not be empty".to_string()));
+    }
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    // Claiming the token in the same statement keeps it single-use under concurrent requests
+    let user_id: Uuid = sqlx::query_scalar(
+        r#"
+        UPDATE password_reset_tokens SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id
+        "#
+    )
+    .bind(hash_token(&payload.token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check reset token: {}", e)))?
+    .ok_or((StatusCode::BAD_REQUEST, "Invalid or expired reset token".to_string()))?;
+
+    // A rejected password rolls the claim back, so the same link can be used to try again
+    let (username, email): (String, String) = sqlx::query_as("SELECT username, email FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+    config.password_policy.check(&payload.new_password, &username, &email).await?;
+
+    sqlx::query("UPDATE users SET password_hash = $1, password_reset_required = FALSE WHERE id = $2")
+        .bind(hash_password(&payload.new_password)?)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update password: {}", e)))?;
+
+    // Any other outstanding links for this account die with this one
+    sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to expire reset tokens: {}", e)))?;
+
+    revocations.revoke_user(&mut tx, user_id).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+    refresh::revoke_all_for_user(&mut tx, user_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+// Signs out every other session, since the old password may be what let someone in
+pub async fn change_password( State(db): State<DbPool>, Extension(config): Extension<Config>, claims: Claims, Json(payload): Json<ChangePassword>) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+    let family_id = Uuid::parse_str(&claims.sid)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid session ID: {}", e)))?;
+
+    if payload.new_password.is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "New password must not be empty".to_string()));
+    }
+
+    let (username, email, password_hash): (String, String, String) = sqlx::query_as("SELECT username, email, password_hash FROM users WHERE id = $1")
+        .bind(user_id)
+        .fetch_one(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+
+    // Guessing the current password counts towards the same lockout as guessing it at login
+    let account_key = throttle::account_key(&email);
+    throttle::check(&db, std::slice::from_ref(&account_key)).await?;
+
+    let parsed_hash = PasswordHash::new(&password_hash)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to parse password hash: {}", e)))?;
+    if get_argon2().verify_password(payload.current_password.as_bytes(), &parsed_hash).is_err() {
+        throttle::record_failure(&db, &config, &account_key, config.login_max_failures_per_account).await?;
+        return Err((StatusCode::FORBIDDEN, "Current password is incorrect".to_string()));
+    }
+    if payload.new_password == payload.current_password {
+        return Err((StatusCode::BAD_REQUEST, "New password must be different from the current one".to_string()));
+    }
+    config.password_policy.check(&payload.new_password, &username, &email).await?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    sqlx::query("UPDATE users SET password_hash = $1 WHERE id = $2")
+        .bind(hash_password(&payload.new_password)?)
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update password: {}", e)))?;
+
+    // Reset links sent for the old password are no longer wanted
+    sqlx::query("UPDATE password_reset_tokens SET used_at = NOW() WHERE user_id = $1 AND used_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to expire reset tokens: {}", e)))?;
+
+    refresh::revoke_all_except(&mut tx, user_id, family_id).await?;
+    throttle::clear(&mut *tx, &account_key).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+pub async fn confirm_email( State((db, _config)): State<(DbPool, Config)>, Json(payload): Json<ConfirmEmail>) -> Result<StatusCode, (StatusCode, String)> {
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let user_id: Uuid = sqlx::query_scalar(
+        r#"
+        UPDATE email_verification_tokens SET used_at = NOW()
+        WHERE token_hash = $1 AND used_at IS NULL AND expires_at > NOW()
+        RETURNING user_id
+        "#
+    )
+    .bind(hash_token(&payload.token))
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check verification token: {}", e)))?
+    .ok_or((StatusCode::BAD_REQUEST, "Invalid or expired verification token".to_string()))?;
+
+    sqlx::query("UPDATE users SET email_verified_at = COALESCE(email_verified_at, NOW()) WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to verify email: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+// Like forgot_password, the answer never says whether the address is registered
+pub async fn resend_verification_email( State((db, config)): State<(DbPool, Config)>, Extension(mailer): Extension<Arc<dyn Mailer>>, Json(payload): Json<ResendVerification>) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id: Option<Uuid> = sqlx::query_scalar("SELECT id FROM users WHERE email = $1 AND email_verified_at IS NULL")
+        .bind(payload.email.trim())
+        .fetch_optional(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch user: {}", e)))?;
+
+    if let Some(user_id) = user_id {
+        send_verification_email(&db, &config, mailer, user_id, payload.email.trim()).await?;
+    }
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+
+
+// Opt-in enrolment for signed-in users; finish it with confirm_totp_enrolment
+pub async fn start_totp_enrolment( State(db): State<DbPool>, Extension(config): Extension<Config>, claims: Claims, ) -> Result<Json<TotpEnrolment>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let state = fetch_totp_state(&mut tx, user_id).await?;
+    let enrolment = begin_totp_enrolment(&mut tx, &config, user_id, &state).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(enrolment))
+}
+
+
+
+pub async fn confirm_totp_enrolment( State(db): State<DbPool>, claims: Claims, Json(payload): Json<SecondFactor>) -> Result<Json<RecoveryCodes>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let state = fetch_totp_state(&mut tx, user_id).await?;
+    if state.totp_enabled {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is already set up".to_string()));
+    }
+
+    let factor = SecondFactor { code: payload.code, recovery_code: None };
+    if !check_second_factor(&mut tx, user_id, &state, &factor).await? {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid verification code".to_string()));
+    }
+
+    let recovery_codes = enable_totp(&mut tx, user_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+
+
+pub async fn regenerate_recovery_codes( State(db): State<DbPool>, claims: Claims, Json(payload): Json<SecondFactor>) -> Result<Json<RecoveryCodes>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let state = fetch_totp_state(&mut tx, user_id).await?;
+    if !state.totp_enabled {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is not set up".to_string()));
+    }
+
+    // Only the authenticator itself can mint new recovery codes
+    let factor = SecondFactor { code: payload.code, recovery_code: None };
+    if !check_second_factor(&mut tx, user_id, &state, &factor).await? {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid verification code".to_string()));
+    }
+
+    let recovery_codes = replace_recovery_codes(&mut tx, user_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(RecoveryCodes { recovery_codes }))
+}
+
+
+
+pub async fn disable_totp( State(db): State<DbPool>, Extension(config): Extension<Config>, claims: Claims, Json(payload): Json<SecondFactor>) -> Result<StatusCode, (StatusCode, String)> {
+
+    if config.mfa_required_roles.contains(&claims.role) {
+        return Err((StatusCode::FORBIDDEN, format!("Two-factor authentication is mandatory for role '{}'", claims.role)));
+    }
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let state = fetch_totp_state(&mut tx, user_id).await?;
+    if !state.totp_enabled {
+        return Err((StatusCode::CONFLICT, "Two-factor authentication is not set up".to_string()));
+    }
+
+    if !check_second_factor(&mut tx, user_id, &state, &payload).await? {
+        return Err((StatusCode::UNAUTHORIZED, "Invalid verification code".to_string()));
+    }
+
+    sqlx::query("UPDATE users SET totp_secret = NULL, totp_enabled_at = NULL, totp_last_used_step = NULL WHERE id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to disable two-factor authentication: {}", e)))?;
+
+    sqlx::query("DELETE FROM totp_recovery_codes WHERE user_id = $1")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to clear recovery codes: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+// Recent sign-ins to the caller's account, so they can spot logins that weren't theirs
+pub async fn get_login_history( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Vec<LoginEvent>>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let events = sqlx::query_as::<_, LoginEvent>(
+        "SELECT id, ip_address, user_agent, success, failure_reason, created_at FROM login_events WHERE user_id = $1 ORDER BY created_at DESC LIMIT 50"
+    )
+    .bind(user_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch login history: {}", e)))?;
+
+    Ok(Json(events))
+}
+
+
+
+// Sessions that can still be used: not revoked, and with a refresh token that hasn't expired
+pub async fn get_sessions( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Vec<Session>>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+    let session_id = Uuid::parse_str(&claims.sid).ok();
+
+    let sessions = sqlx::query_as::<_, Session>(
+        r#"
+        SELECT s.id, s.user_agent, s.ip_address, s.last_seen_ip, s.created_at, s.last_seen_at, s.id IS NOT DISTINCT FROM $2 AS current
+        FROM sessions s
+        WHERE s.user_id = $1 AND s.revoked_at IS NULL
+          AND EXISTS (
+              SELECT 1 FROM refresh_tokens r
+              WHERE r.family_id = s.id AND r.revoked_at IS NULL AND r.rotated_at IS NULL AND r.expires_at > NOW()
+          )
+        ORDER BY s.last_seen_at DESC
+        "#
+    )
+    .bind(user_id)
+    .bind(session_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sessions: {}", e)))?;
+
+    Ok(Json(sessions))
+}
+
+
+
+// Signs one device out: its refresh tokens stop working and its access tokens are refused from now on
+pub async fn revoke_session( State(db): State<DbPool>, Path(session_id): Path<Uuid>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let exists: bool = sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sessions WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL)")
+        .bind(session_id)
+        .bind(user_id)
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch session: {}", e)))?;
+    if !exists {
+        return Err((StatusCode::NOT_FOUND, "Session not found".to_string()));
+    }
+
+    refresh::revoke_family(&mut tx, session_id).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+// Stores the one-time state of a login or link and returns the provider's authorization URL
+async fn begin_oidc_flow(db: &DbPool, oidc: &OidcClient, provider: &OidcProviderConfig, link_user_id: Option<Uuid>) -> Result<String, (StatusCode, String)> {
+    let state = generate_token();
+    let nonce = generate_token();
+    let code_verifier = generate_token();
+
+    sqlx::query(
+        "INSERT INTO oidc_login_states (id, provider, state_hash, code_verifier, nonce, link_user_id, expires_at) VALUES ($1, $2, $3, $4, $5, $6, $7)"
+    )
+    .bind(Uuid::new_v4())
+    .bind(&provider.name)
+    .bind(hash_token(&state))
+    .bind(&code_verifier)
+    .bind(&nonce)
+    .bind(link_user_id)
+    .bind(chrono::Utc::now() + chrono::Duration::seconds(OIDC_LOGIN_TTL_SECS))
+    .execute(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start login: {}", e)))?;
+
+    oidc.authorization_url(provider, &state, &nonce, &code_verifier).await
+        .map_err(|e| (StatusCode::BAD_GATEWAY, e))
+}
+
+
+
+// Public keys for verifying our access tokens, for other services
+pub async fn get_jwks( State((_db, config)): State<(DbPool, Config)>) -> Json<jsonwebtoken::jwk::JwkSet> {
+    Json(config.jwt_keys.jwks())
+}
+
+
+
+// Sends the browser to the identity provider, with PKCE and a one-time state
+pub async fn start_oidc_login( State((db, config)): State<(DbPool, Config)>, Extension(oidc): Extension<Arc<OidcClient>>, Path(provider): Path<String>, ) -> Result<Redirect, (StatusCode, String)> {
+
+    let provider = oidc_provider(&config, &provider)?;
+
+    let url = begin_oidc_flow(&db, &oidc, provider, None).await?;
+
+    Ok(Redirect::to(&url))
+}
+
+
+
+// Same flow as start_oidc_login, but the callback links the provider account to the
+// signed-in user. Returned rather than redirected, since the caller sends a bearer token
+pub async fn start_oidc_link( State(db): State<DbPool>, Extension(config): Extension<Config>, Extension(oidc): Extension<Arc<OidcClient>>, Path(provider): Path<String>, claims: Claims, ) -> Result<Json<OidcLinkStart>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+    let provider = oidc_provider(&config, &provider)?;
+
+    let authorization_url = begin_oidc_flow(&db, &oidc, provider, Some(user_id)).await?;
+
+    Ok(Json(OidcLinkStart { authorization_url }))
+}
+
+
+
+// The page at the provider's redirect URI posts the `code` and `state` it received here,
+// with the user's access token when it is finishing a link
+pub async fn finish_oidc_login( State((db, config)): State<(DbPool, Config)>, Extension(oidc): Extension<Arc<OidcClient>>, Extension(mailer): Extension<Arc<dyn Mailer>>, Path(provider): Path<String>, client: ClientInfo, claims: Option<Claims>, Json(payload): Json<OidcCallback>) -> Result<Json<LoginResponse>, (StatusCode, String)> {
+
+    let provider = oidc_provider(&config, &provider)?;
+    let caller = claims
+        .map(|claims| Uuid::parse_str(&claims.sub))
+        .transpose()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let (code_verifier, nonce, link_user_id, _): (String, String, Option<Uuid>, chrono::DateTime<chrono::Utc>) = sqlx::query_as(
+        "DELETE FROM oidc_login_states WHERE state_hash = $1 AND provider = $2 RETURNING code_verifier, nonce, link_user_id, expires_at"
+    )
+    .bind(hash_token(&payload.state))
+    .bind(&provider.name)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch login state: {}", e)))?
+    .filter(|(_, _, _, expires_at)| *expires_at > chrono::Utc::now())
+    .ok_or((StatusCode::UNAUTHORIZED, "Invalid or expired login state".to_string()))?;
+
+    let pending = PendingLogin { code_verifier, nonce, link_user_id };
+    let identity = oidc.finish(provider, &pending, &payload.code, caller).await
+        .map_err(|e| (StatusCode::UNAUTHORIZED, e))?;
+
+    let role = identity.role(provider)
+        .ok_or((StatusCode::FORBIDDEN, format!("Your {} account is not allowed to sign in here", provider.name)))?;
+
+    let account = link_external_identity(&db, provider, &identity, role, link_user_id).await?;
+
+    if account.suspended {
+        record_login(&db, Some(account.id), &account.email, &client, Some("suspended")).await?;
+        return Err((StatusCode::FORBIDDEN, "Account is suspended".to_string()));
+    }
+    if account.password_reset_required {
+        return Err((StatusCode::FORBIDDEN, "Password reset required: log in with your password and a new_password".to_string()));
+    }
+
+    Ok(Json(complete_login(&db, &config, &mailer, &client, account, None).await?))
+}
+
+
+
+// Keys for integrations such as a firm's case-management system; the full key is only returned here
+pub async fn create_api_key( State(db): State<DbPool>, Extension(config): Extension<Config>, claims: Claims, Json(payload): Json<CreateApiKey>) -> Result<Json<CreatedApiKey>, (StatusCode, String)> {
+
+    api_keys::check_can_create(&claims.role)?;
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let name = payload.name.trim();
+    if name.is_empty() || name.len() > 100 {
+        return Err((StatusCode::BAD_REQUEST, "Name must be between 1 and 100 characters".to_string()));
+    }
+
+    let endpoints = match payload.endpoints {
+        Some(endpoints) if endpoints.is_empty() => {
+            return Err((StatusCode::BAD_REQUEST, "List at least one endpoint, or leave endpoints out".to_string()));
+        }
+        Some(endpoints) => Some(
+            endpoints
+                .iter()
+                .map(|endpoint| api_keys::validate_endpoint(endpoint))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| (StatusCode::BAD_REQUEST, e))?
+        ),
+        None => None,
+    };
+
+    let expires_at = match payload.expires_in_days {
+        Some(days) if !(1..=3650).contains(&days) => {
+            return Err((StatusCode::BAD_REQUEST, "expires_in_days must be between 1 and 3650".to_string()));
+        }
+        Some(days) => Some(chrono::Utc::now() + chrono::Duration::days(days)),
+        None => None,
+    };
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    // Serialises concurrent creations for the same user so the limit holds
+    sqlx::query("SELECT id FROM users WHERE id = $1 FOR UPDATE")
+        .bind(user_id)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to lock user: {}", e)))?;
+
+    let active: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM api_keys WHERE user_id = $1 AND revoked_at IS NULL AND (expires_at IS NULL OR expires_at > NOW())"
+    )
+    .bind(user_id)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to count API keys: {}", e)))?;
+    if active >= config.max_api_keys_per_user {
+        return Err((StatusCode::CONFLICT, format!("You already have {} active API keys, revoke one first", active)));
+    }
+
+    let (prefix, api_key, secret_hash) = api_keys::generate_key();
+
+    let key = sqlx::query_as::<_, ApiKey>(
+        r#"
+        INSERT INTO api_keys (id, user_id, name, prefix, secret_hash, read_only, endpoints, expires_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id, name, prefix, read_only, endpoints, expires_at, last_used_at, last_used_ip, revoked_at, created_at
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(name)
+    .bind(&prefix)
+    .bind(&secret_hash)
+    .bind(payload.read_only)
+    .bind(&endpoints)
+    .bind(expires_at)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create API key: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(CreatedApiKey { key, api_key }))
+}
+
+
+
+pub async fn list_api_keys( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Vec<ApiKey>>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let keys = sqlx::query_as::<_, ApiKey>(
+        "SELECT id, name, prefix, read_only, endpoints, expires_at, last_used_at, last_used_ip, revoked_at, created_at FROM api_keys WHERE user_id = $1 ORDER BY created_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch API keys: {}", e)))?;
+
+    Ok(Json(keys))
+}
+
+
+
+pub async fn revoke_api_key( State(db): State<DbPool>, Path(key_id): Path<Uuid>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))?;
+
+    let revoked = sqlx::query("UPDATE api_keys SET revoked_at = NOW() WHERE id = $1 AND user_id = $2 AND revoked_at IS NULL")
+        .bind(key_id)
+        .bind(user_id)
+        .execute(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to revoke API key: {}", e)))?;
+
+    if revoked.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "API key not found".to_string()));
+    }
 
-    let token = create_jwt(&id.to_string(), &role, &config.jwt_secret)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create JWT: {}", e)))?;
-    Ok(Json(AuthResponse { token, role }))
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header,};
+use jsonwebtoken::{decode, decode_header, encode, errors::{Error, ErrorKind}, Validation};
 use chrono::{Utc, Duration};
+use uuid::Uuid;
+
+use crate::auth::keys::JwtKeys;
 
 #[derive(Debug, Serialize, Deserialize)]
 
@@ -8,25 +11,35 @@ pub struct Claims {
     pub sub: String,
     pub role: String,
     pub exp: usize,
+    pub iat: usize,
+    pub jti: String, // unique per token, so it can be revoked on its own
+    pub sid: String, // refresh token family the token was issued under
 }
 
-pub fn create_jwt(user_id: &str, role: &str, secret: &str) -> Result<String, jsonwebtoken::errors::Error> {
-    let expiration = Utc::now()
-        .checked_add_signed(Duration::hours(24))
-        .ok_or_else(|| jsonwebtoken::errors::Error::from(jsonwebtoken::errors::ErrorKind::InvalidToken))?
+pub fn create_jwt(user_id: &str, role: &str, session_id: &str, keys: &JwtKeys, ttl_secs: i64) -> Result<String, Error> {
+    let now = Utc::now();
+    let expiration = now
+        .checked_add_signed(Duration::seconds(ttl_secs))
+        .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?
         .timestamp() as usize;
 
     let claims = Claims {
         sub: user_id.to_string(),
         role: role.to_string(),
         exp: expiration,
+        iat: now.timestamp() as usize,
+        jti: Uuid::new_v4().to_string(),
+        sid: session_id.to_string(),
     };
-    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_ref()))
+    encode(&keys.header(), &claims, keys.signing_key())
 }
 
-// Will be used by auth_middleware for protected routes
-#[allow(dead_code)]
-pub fn verify_jwt(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
-    let token_data = decode::<Claims>(token, &DecodingKey::from_secret(secret.as_ref()), &jsonwebtoken::Validation::default())?;
+pub fn verify_jwt(token: &str, keys: &JwtKeys) -> Result<Claims, Error> {
+    // The header names the key; tokens from retired keys or other algorithms are refused
+    let header = decode_header(token)?;
+    let (key, algorithm) = keys
+        .verification_key(&header)
+        .ok_or_else(|| Error::from(ErrorKind::InvalidToken))?;
+    let token_data = decode::<Claims>(token, key, &Validation::new(algorithm))?;
     Ok(token_data.claims)
 }
\ No newline at end of file
@@ -0,0 +1,389 @@
+use std::fs;
+
+use aws_lc_rs::{rsa, signature::{Ed25519KeyPair, KeyPair}};
+use base64::{Engine, engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD}};
+use jsonwebtoken::{
+    Algorithm, DecodingKey, EncodingKey, Header,
+    jwk::{
+        AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm,
+        OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+        ThumbprintHash,
+    },
+};
+use sha2::{Digest, Sha256};
+
+// SubjectPublicKeyInfo header of an Ed25519 public key; the 32 key bytes follow it
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+struct VerificationKey {
+    kid: Option<String>,
+    algorithm: Algorithm,
+    key: DecodingKey,
+    jwk: Option<Jwk>, // None for the HMAC secret, which is never published
+}
+
+/// The key access tokens are signed with, plus every key still accepted when
+/// verifying them. Asymmetric keys are identified by their RFC 7638 thumbprint,
+/// sent as `kid` in the token header.
+pub struct JwtKeys {
+    algorithm: Algorithm,
+    kid: Option<String>,
+    signing: EncodingKey,
+    verification: Vec<VerificationKey>,
+}
+
+impl JwtKeys {
+    /// HS256 with a shared secret.
+    pub fn hmac(secret: &str) -> Self {
+        JwtKeys {
+            algorithm: Algorithm::HS256,
+            kid: None,
+            signing: EncodingKey::from_secret(secret.as_bytes()),
+            verification: vec![hmac_verification_key(secret)],
+        }
+    }
+
+    /// RS256 or EdDSA with a private key in PEM format. Tokens signed with any of
+    /// `verification_key_files` (public or private keys), or with `hmac_secret`,
+    /// are still accepted, so keys can be rotated without logging everyone out.
+    pub fn from_files(algorithm: Algorithm, signing_key_file: &str, verification_key_files: &[String], hmac_secret: Option<&str>) -> Result<Self, String> {
+        let (signing, signing_jwk, key_algorithm) = load_private_key(signing_key_file)?;
+        if key_algorithm != algorithm {
+            return Err(format!("{} is not a {:?} key", signing_key_file, algorithm));
+        }
+
+        let mut verification = vec![asymmetric_verification_key(signing_jwk, algorithm)?];
+        for file in verification_key_files {
+            let (jwk, algorithm) = load_public_key(file)?;
+            verification.push(asymmetric_verification_key(jwk, algorithm)?);
+        }
+        if let Some(secret) = hmac_secret {
+            verification.push(hmac_verification_key(secret));
+        }
+
+        Ok(JwtKeys {
+            algorithm,
+            kid: verification[0].kid.clone(),
+            signing,
+            verification,
+        })
+    }
+
+    pub fn header(&self) -> Header {
+        let mut header = Header::new(self.algorithm);
+        header.kid = self.kid.clone();
+        header
+    }
+
+    pub fn signing_key(&self) -> &EncodingKey {
+        &self.signing
+    }
+
+    /// The key matching a token's `kid` and `alg`, if it is still accepted.
+    pub fn verification_key(&self, header: &Header) -> Option<(&DecodingKey, Algorithm)> {
+        self.verification
+            .iter()
+            .find(|key| key.kid == header.kid && key.algorithm == header.alg)
+            .map(|key| (&key.key, key.algorithm))
+    }
+
+    /// The public verification keys, for `/.well-known/jwks.json`.
+    pub fn jwks(&self) -> JwkSet {
+        JwkSet {
+            keys: self.verification.iter().filter_map(|key| key.jwk.clone()).collect(),
+        }
+    }
+}
+
+fn hmac_verification_key(secret: &str) -> VerificationKey {
+    VerificationKey {
+        kid: None,
+        algorithm: Algorithm::HS256,
+        key: DecodingKey::from_secret(secret.as_bytes()),
+        jwk: None,
+    }
+}
+
+// RFC 7638: SHA-256 over the required members in lexicographic order. jsonwebtoken
+// leaves `crv` unquoted for Ed25519 keys, so those are hashed here.
+fn thumbprint(jwk: &Jwk) -> String {
+    match &jwk.algorithm {
+        AlgorithmParameters::OctetKeyPair(params) => {
+            let canonical = format!(r#"{{"crv":"Ed25519","kty":"OKP","x":"{}"}}"#, params.x);
+            URL_SAFE_NO_PAD.encode(Sha256::digest(canonical))
+        }
+        _ => jwk.thumbprint(ThumbprintHash::SHA256),
+    }
+}
+
+fn asymmetric_verification_key(mut jwk: Jwk, algorithm: Algorithm) -> Result<VerificationKey, String> {
+    let kid = thumbprint(&jwk);
+    jwk.common = CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(match algorithm {
+            Algorithm::EdDSA => KeyAlgorithm::EdDSA,
+            _ => KeyAlgorithm::RS256,
+        }),
+        key_id: Some(kid.clone()),
+        ..Default::default()
+    };
+    let key = DecodingKey::from_jwk(&jwk)
+        .map_err(|e| format!("Failed to build verification key: {}", e))?;
+
+    Ok(VerificationKey { kid: Some(kid), algorithm, key, jwk: Some(jwk) })
+}
+
+fn rsa_jwk(public_key: &rsa::PublicKey) -> Jwk {
+    Jwk {
+        common: CommonParameters::default(),
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: URL_SAFE_NO_PAD.encode(public_key.modulus().big_endian_without_leading_zero()),
+            e: URL_SAFE_NO_PAD.encode(public_key.exponent().big_endian_without_leading_zero()),
+        }),
+    }
+}
+
+fn ed25519_jwk(public_key: &[u8]) -> Jwk {
+    Jwk {
+        common: CommonParameters::default(),
+        algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+            key_type: OctetKeyPairType::OctetKeyPair,
+            curve: EllipticCurve::Ed25519,
+            x: URL_SAFE_NO_PAD.encode(public_key),
+        }),
+    }
+}
+
+// Reads the first PEM block in the file: (label, DER contents)
+fn read_pem(path: &str) -> Result<(String, Vec<u8>), String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read key file {}: {}", path, e))?;
+
+    let invalid = || format!("{} is not a PEM file", path);
+    let (_, after_begin) = text.split_once("-----BEGIN ").ok_or_else(invalid)?;
+    let (label, after_label) = after_begin.split_once("-----").ok_or_else(invalid)?;
+    let (body, _) = after_label.split_once("-----END ").ok_or_else(invalid)?;
+
+    let body: String = body.split_whitespace().collect();
+    let der = STANDARD.decode(body)
+        .map_err(|e| format!("Failed to decode key file {}: {}", path, e))?;
+
+    Ok((label.to_string(), der))
+}
+
+// Splits one DER element off the front of `input`: (tag, contents, rest)
+fn der_next(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, input) = input.split_first()?;
+    let (&len, mut input) = input.split_first()?;
+    let len = if len < 0x80 {
+        len as usize
+    } else {
+        let count = (len & 0x7f) as usize;
+        if count == 0 || count > 4 || input.len() < count {
+            return None;
+        }
+        let (bytes, rest) = input.split_at(count);
+        input = rest;
+        bytes.iter().fold(0usize, |acc, b| (acc << 8) | *b as usize)
+    };
+    if input.len() < len {
+        return None;
+    }
+    let (contents, rest) = input.split_at(len);
+    Some((tag, contents, rest))
+}
+
+// The PKCS#1 RSAPrivateKey wrapped inside a PKCS#8 PrivateKeyInfo
+fn pkcs8_private_key(pkcs8: &[u8]) -> Option<&[u8]> {
+    let (0x30, info, _) = der_next(pkcs8)? else {
+        return None;
+    };
+    let (_, _, rest) = der_next(info)?; // version
+    let (_, _, rest) = der_next(rest)?; // algorithm
+    let (0x04, key, _) = der_next(rest)? else {
+        return None;
+    };
+    Some(key)
+}
+
+fn load_private_key(path: &str) -> Result<(EncodingKey, Jwk, Algorithm), String> {
+    let (label, der) = read_pem(path)?;
+    let rejected = |e| format!("Unsupported private key in {}: {}", path, e);
+
+    match label.as_str() {
+        "RSA PRIVATE KEY" => {
+            let key_pair = rsa::KeyPair::from_der(&der).map_err(rejected)?;
+            Ok((EncodingKey::from_rsa_der(&der), rsa_jwk(key_pair.public_key()), Algorithm::RS256))
+        }
+        "PRIVATE KEY" => {
+            if let Ok(key_pair) = rsa::KeyPair::from_pkcs8(&der) {
+                let pkcs1 = pkcs8_private_key(&der)
+                    .ok_or_else(|| format!("Malformed private key in {}", path))?;
+                return Ok((EncodingKey::from_rsa_der(pkcs1), rsa_jwk(key_pair.public_key()), Algorithm::RS256));
+            }
+            let key_pair = Ed25519KeyPair::from_pkcs8(&der).map_err(rejected)?;
+            Ok((EncodingKey::from_ed_der(&der), ed25519_jwk(key_pair.public_key().as_ref()), Algorithm::EdDSA))
+        }
+        other => Err(format!("Expected a private key in {}, found {}", path, other)),
+    }
+}
+
+// Verification keys may be given as public keys or as retired private keys
+fn load_public_key(path: &str) -> Result<(Jwk, Algorithm), String> {
+    let (label, der) = read_pem(path)?;
+
+    match label.as_str() {
+        "PUBLIC KEY" if der.len() == 44 && der.starts_with(&ED25519_SPKI_PREFIX) => {
+            Ok((ed25519_jwk(&der[ED25519_SPKI_PREFIX.len()..]), Algorithm::EdDSA))
+        }
+        "PUBLIC KEY" | "RSA PUBLIC KEY" => {
+            let public_key = rsa::PublicKey::from_der(&der)
+                .map_err(|e| format!("Unsupported public key in {}: {}", path, e))?;
+            Ok((rsa_jwk(&public_key), Algorithm::RS256))
+        }
+        _ => load_private_key(path).map(|(_, jwk, algorithm)| (jwk, algorithm)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use aws_lc_rs::{encoding::{AsDer, Pkcs8V1Der, PublicKeyX509Der}, rand::SystemRandom, rsa::KeySize};
+
+    use super::*;
+
+    // Writes `der` as a PEM file in the temp directory and returns its path
+    fn pem_file(name: &str, label: &str, der: &[u8]) -> String {
+        let body = STANDARD.encode(der).as_bytes().chunks(64).map(|line| String::from_utf8_lossy(line).into_owned()).collect::<Vec<_>>().join("\n");
+        let path = std::env::temp_dir().join(format!("bailbridge-keys-{}-{}.pem", std::process::id(), name));
+        fs::write(&path, format!("-----BEGIN {label}-----\n{body}\n-----END {label}-----\n")).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    fn ed25519_pkcs8() -> Vec<u8> {
+        Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap().as_ref().to_vec()
+    }
+
+    #[test]
+    fn der_next_reads_short_and_long_lengths() {
+        assert_eq!(der_next(&[0x04, 0x02, 0xaa, 0xbb, 0xcc]), Some((0x04, &[0xaa, 0xbb][..], &[0xcc][..])));
+
+        let mut long = vec![0x30, 0x81, 0x80];
+        long.extend([0u8; 0x80]);
+        let (tag, contents, rest) = der_next(&long).unwrap();
+        assert_eq!((tag, contents.len(), rest.len()), (0x30, 0x80, 0));
+    }
+
+    #[test]
+    fn der_next_rejects_truncated_input() {
+        assert_eq!(der_next(&[]), None);
+        assert_eq!(der_next(&[0x04]), None);
+        assert_eq!(der_next(&[0x04, 0x03, 0xaa]), None);
+        assert_eq!(der_next(&[0x30, 0x82, 0x01]), None);
+        assert_eq!(der_next(&[0x30, 0x80]), None);
+    }
+
+    #[test]
+    fn pkcs8_private_key_unwraps_the_inner_key() {
+        // An Ed25519 PKCS#8 holds the 32-byte seed as an OCTET STRING inside the OCTET STRING
+        let pkcs8 = ed25519_pkcs8();
+        let key = pkcs8_private_key(&pkcs8).unwrap();
+        assert_eq!((key.len(), key[0], key[1]), (34, 0x04, 0x20));
+
+        assert_eq!(pkcs8_private_key(&[0x04, 0x00]), None);
+        assert_eq!(pkcs8_private_key(&[]), None);
+    }
+
+    #[test]
+    fn read_pem_takes_the_first_block() {
+        let path = pem_file("read", "PUBLIC KEY", &[1, 2, 3, 4]);
+        assert_eq!(read_pem(&path).unwrap(), ("PUBLIC KEY".to_string(), vec![1, 2, 3, 4]));
+
+        fs::write(&path, "not a key").unwrap();
+        assert!(read_pem(&path).unwrap_err().contains("is not a PEM file"));
+        fs::remove_file(&path).unwrap();
+        assert!(read_pem(&path).unwrap_err().starts_with("Failed to read key file"));
+    }
+
+    #[test]
+    fn ed25519_kid_is_the_rfc_8037_thumbprint() {
+        // RFC 8037, appendix A.3
+        let x = URL_SAFE_NO_PAD.decode("11qYAYKxCrfVS_7TyWQHOg7hcvPapiMlrwIaaPcHURo").unwrap();
+        let key = asymmetric_verification_key(ed25519_jwk(&x), Algorithm::EdDSA).unwrap();
+        assert_eq!(key.kid.as_deref(), Some("kPrK_qmxVWaYVA9wwBF6Iuo3vVzz7TxHCTwXBygrS4k"));
+    }
+
+    #[test]
+    fn rsa_kid_hashes_the_required_members_in_order() {
+        let key_pair = rsa::KeyPair::generate(KeySize::Rsa2048).unwrap();
+        let jwk = rsa_jwk(key_pair.public_key());
+        let AlgorithmParameters::RSA(params) = &jwk.algorithm else {
+            panic!("not an RSA key");
+        };
+        assert_eq!(params.e, "AQAB");
+        let canonical = format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, params.e, params.n);
+        let expected = URL_SAFE_NO_PAD.encode(Sha256::digest(canonical));
+
+        let key = asymmetric_verification_key(jwk, Algorithm::RS256).unwrap();
+        assert_eq!(key.kid, Some(expected));
+    }
+
+    #[test]
+    fn rsa_keys_load_from_pkcs8_pkcs1_and_spki() {
+        let key_pair = rsa::KeyPair::generate(KeySize::Rsa2048).unwrap();
+        let pkcs8: Pkcs8V1Der = key_pair.as_der().unwrap();
+        let spki: PublicKeyX509Der = key_pair.public_key().as_der().unwrap();
+        let pkcs1 = pkcs8_private_key(pkcs8.as_ref()).unwrap();
+
+        let pkcs8_path = pem_file("rsa-pkcs8", "PRIVATE KEY", pkcs8.as_ref());
+        let pkcs1_path = pem_file("rsa-pkcs1", "RSA PRIVATE KEY", pkcs1);
+        let spki_path = pem_file("rsa-spki", "PUBLIC KEY", spki.as_ref());
+
+        let (_, from_pkcs8, algorithm) = load_private_key(&pkcs8_path).unwrap();
+        let (_, from_pkcs1, _) = load_private_key(&pkcs1_path).unwrap();
+        let (from_spki, public_algorithm) = load_public_key(&spki_path).unwrap();
+        assert_eq!((algorithm, public_algorithm), (Algorithm::RS256, Algorithm::RS256));
+        assert_eq!(from_pkcs8, from_pkcs1);
+        assert_eq!(from_pkcs8, from_spki);
+
+        assert!(load_private_key(&spki_path).unwrap_err().contains("Expected a private key"));
+        for path in [pkcs8_path, pkcs1_path, spki_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+
+    #[test]
+    fn rotated_keys_stay_accepted_and_published() {
+        let current = ed25519_pkcs8();
+        let retired = Ed25519KeyPair::from_pkcs8(&ed25519_pkcs8()).unwrap();
+        let mut retired_spki = ED25519_SPKI_PREFIX.to_vec();
+        retired_spki.extend_from_slice(retired.public_key().as_ref());
+
+        let current_path = pem_file("ed-current", "PRIVATE KEY", &current);
+        let retired_path = pem_file("ed-retired", "PUBLIC KEY", &retired_spki);
+        let keys = JwtKeys::from_files(Algorithm::EdDSA, &current_path, std::slice::from_ref(&retired_path), Some("secret")).unwrap();
+
+        let header = keys.header();
+        assert_eq!(header.alg, Algorithm::EdDSA);
+        assert!(keys.verification_key(&header).is_some());
+
+        // Both public keys are published under their thumbprints; the HMAC secret is not
+        let jwks = keys.jwks();
+        assert_eq!(jwks.keys.len(), 2);
+        assert_eq!(jwks.keys[0].common.key_id, header.kid);
+        let retired_kid = jwks.keys[1].common.key_id.clone();
+        assert_eq!(retired_kid, Some(thumbprint(&ed25519_jwk(retired.public_key().as_ref()))));
+
+        let mut retired_header = Header::new(Algorithm::EdDSA);
+        retired_header.kid = retired_kid;
+        assert!(keys.verification_key(&retired_header).is_some());
+        assert!(keys.verification_key(&Header::new(Algorithm::HS256)).is_some());
+        assert!(keys.verification_key(&Header::new(Algorithm::EdDSA)).is_none());
+
+        let mismatch = JwtKeys::from_files(Algorithm::RS256, &current_path, &[], None).err().unwrap();
+        assert!(mismatch.contains("is not a RS256 key"));
+        for path in [current_path, retired_path] {
+            fs::remove_file(path).unwrap();
+        }
+    }
+}
@@ -1,12 +1,73 @@
 use axum::{
-    extract::{State, FromRequestParts},
+    extract::{State, FromRequestParts, OptionalFromRequestParts, ConnectInfo, MatchedPath},
     http::{Request, StatusCode, request::Parts},
     middleware::Next,
     response::Response,
     body::Body,
 };
 
-use crate::{auth::jwt::{verify_jwt, Claims}, config::Config};
+use std::{net::SocketAddr, sync::Arc};
+
+use uuid::Uuid;
+
+use crate::{
+    audit::middlewares::{client_ip, AuditActor},
+    auth::{api_keys, sessions, jwt::{verify_jwt, Claims}, revocation::RevocationStore},
+    config::Config,
+    db::DbPool,
+    models::VerificationStatus,
+};
+
+#[derive(sqlx::FromRow)]
+struct AccountState {
+    role: String,
+    verification_status: VerificationStatus,
+    email_verified: bool,
+    suspended: bool,
+    password_reset_required: bool,
+    totp_enabled: bool,
+}
+
+// A valid signature is not enough: the account behind the token must still be in good standing
+async fn ensure_account_active(db: &DbPool, config: &Config, claims: &Claims) -> Result<(), (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token".to_string()))?;
+
+    let account = sqlx::query_as::<_, AccountState>(
+        "SELECT role, verification_status, email_verified_at IS NOT NULL AS email_verified, suspended_at IS NOT NULL AS suspended, password_reset_required, totp_enabled_at IS NOT NULL AS totp_enabled FROM users WHERE id = $1"
+    )
+        .bind(user_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch account: {}", e)))?
+        .ok_or((StatusCode::UNAUTHORIZED, "Account no longer exists".to_string()))?;
+
+    if account.suspended {
+        return Err((StatusCode::FORBIDDEN, "Account is suspended".to_string()));
+    }
+    if account.password_reset_required {
+        return Err((StatusCode::UNAUTHORIZED, "Password reset required, please log in again".to_string()));
+    }
+    if account.role != claims.role {
+        return Err((StatusCode::UNAUTHORIZED, "Account role has changed, please log in again".to_string()));
+    }
+
+    // Tokens issued before 2FA became mandatory for this role don't count
+    if config.mfa_required_roles.contains(&account.role) && !account.totp_enabled {
+        return Err((StatusCode::FORBIDDEN, "Two-factor authentication must be set up, please log in again".to_string()));
+    }
+
+    // Lawyers and judges see case data, so their address has to be confirmed first
+    if (account.role == "lawyer" || account.role == "judge") && !account.email_verified {
+        return Err((StatusCode::FORBIDDEN, "Email address has not been verified".to_string()));
+    }
+
+    match account.verification_status {
+        VerificationStatus::Verified => Ok(()),
+        VerificationStatus::Pending => Err((StatusCode::FORBIDDEN, "Account is awaiting verification by an admin".to_string())),
+        VerificationStatus::Rejected => Err((StatusCode::FORBIDDEN, "Account verification was rejected".to_string())),
+    }
+}
 
 impl<S> FromRequestParts<S> for Claims
 where
@@ -24,12 +85,64 @@ where
             .and_then(|h| h.to_str().ok())
             .map(|s| s.to_string());
 
+        let api_key = parts
+            .headers
+            .get("X-Api-Key")
+            .and_then(|h| h.to_str().ok())
+            .map(|s| s.to_string());
+
+        // API keys are scoped to routes, so they need to know which one is being called
+        let method = parts.method.clone();
+        let route = parts
+            .extensions
+            .get::<MatchedPath>()
+            .map(|p| p.as_str().to_string())
+            .unwrap_or_else(|| parts.uri.path().to_string());
         let config = parts
             .extensions
             .get::<Config>()
             .cloned();
 
+        let ip_address = client_ip(
+            &parts.headers,
+            parts.extensions.get::<ConnectInfo<SocketAddr>>(),
+            config.as_ref().map(|config| config.trusted_proxies.as_slice()).unwrap_or_default(),
+        );
+
+        let db = parts
+            .extensions
+            .get::<DbPool>()
+            .cloned();
+
+        let revocations = parts
+            .extensions
+            .get::<Arc<RevocationStore>>()
+            .cloned();
+
+        let actor = parts
+            .extensions
+            .get::<AuditActor>()
+            .cloned();
+
         async move {
+            let config = config
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Config not found".to_string()))?;
+
+            let db = db
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Database not found".to_string()))?;
+
+            if auth_header.is_none() {
+                if let Some(api_key) = api_key {
+                    let (key_id, claims) = api_keys::authenticate(&db, &config, &api_key, &method, &route).await?;
+                    ensure_account_active(&db, &config, &claims).await?;
+                    api_keys::record_use(&db, key_id, ip_address.as_deref()).await?;
+                    if let Some(actor) = &actor {
+                        actor.record(&claims);
+                    }
+                    return Ok(claims);
+                }
+            }
+
             let auth_header = auth_header
                 .ok_or((StatusCode::UNAUTHORIZED, "Missing authorization header".to_string()))?;
 
@@ -37,12 +150,41 @@ where
                 .strip_prefix("Bearer ")
                 .ok_or((StatusCode::UNAUTHORIZED, "Invalid authorization format".to_string()))?;
 
-            let config = config
-                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Config not found".to_string()))?;
+            let claims = verify_jwt(token, &config.jwt_keys)
+                .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token".to_string()))?;
+
+            let revocations = revocations
+                .ok_or((StatusCode::INTERNAL_SERVER_ERROR, "Revocation store not found".to_string()))?;
+            if revocations.is_revoked(&claims).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))? {
+                return Err((StatusCode::UNAUTHORIZED, "Token has been revoked".to_string()));
+            }
+
+            sessions::check(&db, &claims, ip_address.as_deref()).await?;
+
+            ensure_account_active(&db, &config, &claims).await?;
+
+            // Lets the audit log name the caller without checking the token a second time
+            if let Some(actor) = &actor {
+                actor.record(&claims);
+            }
+
+            Ok(claims)
+        }
+    }
+}
+
+// For routes that work signed in or not: no credentials is `None`, bad ones are still refused
+impl<S> OptionalFromRequestParts<S> for Claims
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
 
-            verify_jwt(token, &config.jwt_secret)
-                .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid token".to_string()))
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Option<Self>, Self::Rejection> {
+        if !parts.headers.contains_key("Authorization") && !parts.headers.contains_key("X-Api-Key") {
+            return Ok(None);
         }
+        <Claims as FromRequestParts<S>>::from_request_parts(parts, state).await.map(Some)
     }
 }
 
@@ -64,7 +206,7 @@ pub async fn auth_middleware( State(config): State<Config>, mut req: Request<Bod
         return Err(StatusCode::UNAUTHORIZED);
     }
 
-    verify_jwt(token, &req.extensions().get::<Config>().unwrap().jwt_secret)
+    verify_jwt(token, &req.extensions().get::<Config>().unwrap().jwt_keys)
         .map_err(|_| StatusCode::UNAUTHORIZED)?;
 
     Ok(next.run(req).await)
@@ -1,3 +1,13 @@
 pub mod handlers;
 pub mod middlewares;
 pub mod jwt;
+pub mod keys;
+pub mod refresh;
+pub mod revocation;
+pub mod tokens;
+pub mod totp;
+pub mod throttle;
+pub mod oidc;
+pub mod api_keys;
+pub mod sessions;
+pub mod password_policy;
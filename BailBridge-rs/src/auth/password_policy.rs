@@ -0,0 +1,447 @@
+use std::{collections::HashMap, fs, io::ErrorKind, path::{Path, PathBuf}, sync::OnceLock};
+
+use axum::http::StatusCode;
+use sha1::{Digest, Sha1};
+
+// Caps the work Argon2 and the strength estimate do per request
+const MAX_LENGTH: usize = 128;
+
+// Guess counts at which scores 1 to 4 start, as in zxcvbn
+const SCORE_THRESHOLDS: [f64; 4] = [1e3, 1e6, 1e8, 1e10];
+
+// Guesses per character that no pattern explains
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+// A multi-character pattern never counts as fewer guesses than this
+const MIN_PATTERN_GUESSES: f64 = 50.0;
+
+const REFERENCE_YEAR: i32 = 2026;
+
+// Most common first: a word's position is roughly how soon an attacker tries it
+const COMMON_WORDS: &[&str] = &[
+    "password", "123456", "qwerty", "iloveyou", "admin", "welcome", "letmein", "monkey", "dragon",
+    "abc123", "football", "baseball", "cricket", "sunshine", "princess", "master", "shadow", "superman",
+    "batman", "trustno1", "hello", "freedom", "whatever", "login", "starwars", "passw0rd", "michael",
+    "jennifer", "charlie", "computer", "secret", "summer", "winter", "spring", "autumn", "love",
+    "lovely", "angel", "blessed", "happy", "lucky", "money", "power", "god", "jesus", "family",
+    "friend", "friends", "flower", "orange", "banana", "apple", "cheese", "chocolate", "cookie",
+    "pepper", "ginger", "tiger", "lion", "eagle", "killer", "hunter", "ranger", "soccer", "hockey",
+    "tennis", "golf", "music", "guitar", "silver", "golden", "diamond", "purple", "yellow", "black",
+    "white", "red", "blue", "green", "pink", "mother", "father", "sister", "brother", "daughter",
+    "baby", "sweet", "honey", "darling", "forever", "always", "heaven", "magic", "mustang", "ferrari",
+    "internet", "google", "facebook", "samsung", "nokia", "india", "bharat", "hindustan", "delhi",
+    "mumbai", "kolkata", "chennai", "bangalore", "hyderabad", "pune", "krishna", "ganesh", "shiva",
+    "sairam", "omsairam", "jaihind", "rahul", "amit", "priya", "pooja", "neha", "rohit", "vijay",
+    "sachin", "dhoni", "virat", "january", "february", "march", "april", "may", "june", "july",
+    "august", "september", "october", "november", "december", "monday", "tuesday", "wednesday",
+    "thursday", "friday", "saturday", "sunday", "test", "testing", "user", "guest", "default",
+    "changeme", "access", "system", "server", "office", "welcome1", "bail", "bridge", "bailbridge",
+    "court", "judge", "lawyer", "advocate", "justice", "law", "legal", "police", "case", "order",
+    "hearing", "surety", "clerk", "supreme", "highcourt", "constitution", "freedom1", "victory",
+    "success", "winner", "champion", "legend", "hero", "king", "queen", "prince", "knight", "star",
+    "sun", "moon", "sky", "ocean", "river", "mountain", "fire", "water", "earth", "storm", "thunder",
+];
+
+const KEYBOARD_ROWS: &[&str] = &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm", "qazwsx", "1qaz2wsx"];
+
+fn common_word_ranks() -> &'static HashMap<&'static str, usize> {
+    static RANKS: OnceLock<HashMap<&'static str, usize>> = OnceLock::new();
+    RANKS.get_or_init(|| {
+        let mut ranks = HashMap::new();
+        for (i, word) in COMMON_WORDS.iter().enumerate() {
+            ranks.entry(*word).or_insert(i + 1);
+        }
+        ranks
+    })
+}
+
+// Common character substitutions, undone before looking a word up
+fn unleet(c: char) -> char {
+    match c {
+        '4' | '@' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' => 't',
+        _ => c,
+    }
+}
+
+fn char_cardinality(c: char) -> f64 {
+    if c.is_ascii_digit() {
+        10.0
+    } else if c.is_ascii_lowercase() || c.is_ascii_uppercase() {
+        26.0
+    } else {
+        33.0
+    }
+}
+
+fn dictionary_guesses(segment: &[char], user_inputs: &[String]) -> Option<f64> {
+    let lower: String = segment.iter().flat_map(|c| c.to_lowercase()).collect();
+    let unleeted: String = lower.chars().map(unleet).collect();
+    let reversed: String = unleeted.chars().rev().collect();
+
+    let rank = |word: &str| {
+        if user_inputs.iter().any(|input| input == word) {
+            Some(1)
+        } else {
+            common_word_ranks().get(word).copied()
+        }
+    };
+
+    let (rank, reversed_match) = match (rank(&lower), rank(&unleeted), rank(&reversed)) {
+        (Some(rank), _, _) => (rank, false),
+        (None, Some(rank), _) => (rank, false),
+        (None, None, Some(rank)) => (rank, true),
+        (None, None, None) => return None,
+    };
+
+    let mut guesses = rank as f64;
+    if segment.iter().any(|c| c.is_uppercase()) {
+        guesses *= 2.0;
+    }
+    if lower != unleeted {
+        guesses *= 2.0;
+    }
+    if reversed_match {
+        guesses *= 2.0;
+    }
+    Some(guesses)
+}
+
+// abc, 4321, xyz: every step moves one code point the same way
+fn sequence_guesses(segment: &[char]) -> Option<f64> {
+    if segment.len() < 3 {
+        return None;
+    }
+    let delta = segment[1] as i64 - segment[0] as i64;
+    if delta.abs() != 1 || segment.windows(2).any(|w| w[1] as i64 - w[0] as i64 != delta) {
+        return None;
+    }
+
+    let start = segment[0];
+    let base = if "aAzZ019".contains(start) { 4.0 } else { char_cardinality(start) };
+    let direction = if delta < 0 { 2.0 } else { 1.0 };
+    Some(base * segment.len() as f64 * direction)
+}
+
+fn keyboard_guesses(segment: &[char]) -> Option<f64> {
+    if segment.len() < 4 {
+        return None;
+    }
+    let lower: String = segment.iter().flat_map(|c| c.to_lowercase()).collect();
+    let reversed: String = lower.chars().rev().collect();
+    KEYBOARD_ROWS
+        .iter()
+        .any(|row| row.contains(&lower) || row.contains(&reversed))
+        .then_some(40.0 * segment.len() as f64)
+}
+
+// aaaa, or a chunk typed more than once: abcabc
+fn repeat_guesses(segment: &[char], user_inputs: &[String]) -> Option<f64> {
+    let len = segment.len();
+    if len >= 3 && segment.iter().all(|c| *c == segment[0]) {
+        return Some(char_cardinality(segment[0]) * len as f64);
+    }
+    (2..=len / 2)
+        .filter(|size| len.is_multiple_of(*size))
+        .find(|size| segment.chunks(*size).all(|chunk| chunk == &segment[..*size]))
+        .map(|size| estimate_guesses(&segment[..size], user_inputs) * (len / size) as f64)
+}
+
+fn year_guesses(segment: &[char]) -> Option<f64> {
+    if segment.len() != 4 || !segment.iter().all(char::is_ascii_digit) {
+        return None;
+    }
+    let year: i32 = segment.iter().collect::<String>().parse().ok()?;
+    (1900..=2099).contains(&year)
+        .then(|| (year - REFERENCE_YEAR).abs().max(20) as f64)
+}
+
+fn pattern_guesses(segment: &[char], user_inputs: &[String]) -> Option<f64> {
+    [
+        dictionary_guesses(segment, user_inputs),
+        sequence_guesses(segment),
+        keyboard_guesses(segment),
+        repeat_guesses(segment, user_inputs),
+        year_guesses(segment),
+    ]
+    .into_iter()
+    .flatten()
+    .reduce(f64::min)
+    .map(|guesses| guesses.max(MIN_PATTERN_GUESSES))
+}
+
+// Fewest guesses over every way of splitting the password into patterns and random characters
+fn estimate_guesses(password: &[char], user_inputs: &[String]) -> f64 {
+    let mut best = vec![1.0; password.len() + 1];
+    for end in 1..=password.len() {
+        best[end] = best[end - 1] * BRUTEFORCE_CARDINALITY;
+        for start in 0..end - 1 {
+            if let Some(guesses) = pattern_guesses(&password[start..end], user_inputs) {
+                best[end] = f64::min(best[end], best[start] * guesses);
+            }
+        }
+    }
+    best[password.len()]
+}
+
+/// zxcvbn-style strength score: 0 when a password falls within a thousand guesses,
+/// 4 when it takes more than ten billion. `user_inputs` (username, email) count as
+/// the first words an attacker would try.
+pub fn strength_score(password: &str, user_inputs: &[&str]) -> u8 {
+    let chars: Vec<char> = password.chars().collect();
+    let user_inputs: Vec<String> = user_inputs.iter().map(|input| input.to_lowercase()).collect();
+    let guesses = estimate_guesses(&chars, &user_inputs);
+    SCORE_THRESHOLDS.iter().filter(|threshold| guesses >= **threshold).count() as u8
+}
+
+fn sha1_hex(password: &str) -> String {
+    hex::encode_upper(Sha1::digest(password.as_bytes()))
+}
+
+/// A local copy of a breached-password corpus such as Have I Been Pwned's, keyed
+/// by SHA-1. Passwords never leave the server.
+pub enum BreachedPasswords {
+    /// A directory of range files, one per 5-character hash prefix (`21BD1.txt`),
+    /// each holding `SUFFIX:COUNT` lines. Only the file for a password's prefix is read.
+    Ranges { dir: PathBuf, min_count: u64 },
+    /// A single file of `HASH:COUNT` lines, loaded into memory at startup.
+    Hashes(Vec<[u8; 20]>),
+}
+
+impl BreachedPasswords {
+    /// Hashes seen fewer than `min_count` times are ignored.
+    pub fn open(path: &str, min_count: u64) -> Result<Self, String> {
+        let metadata = fs::metadata(path)
+            .map_err(|e| format!("Failed to open breached password list {}: {}", path, e))?;
+        if metadata.is_dir() {
+            return Ok(BreachedPasswords::Ranges { dir: PathBuf::from(path), min_count });
+        }
+
+        let text = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read breached password list {}: {}", path, e))?;
+        let mut hashes = Vec::new();
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let (hash, count) = parse_line(line);
+            if count < min_count {
+                continue;
+            }
+            let mut bytes = [0u8; 20];
+            hex::decode_to_slice(hash, &mut bytes)
+                .map_err(|_| format!("Invalid hash in breached password list {}: {}", path, line))?;
+            hashes.push(bytes);
+        }
+        hashes.sort_unstable();
+        hashes.dedup();
+
+        Ok(BreachedPasswords::Hashes(hashes))
+    }
+
+    pub async fn contains(&self, password: &str) -> Result<bool, String> {
+        let hash = sha1_hex(password);
+
+        match self {
+            BreachedPasswords::Hashes(hashes) => {
+                let mut bytes = [0u8; 20];
+                hex::decode_to_slice(&hash, &mut bytes).map_err(|e| e.to_string())?;
+                Ok(hashes.binary_search(&bytes).is_ok())
+            }
+            BreachedPasswords::Ranges { dir, min_count } => {
+                let (prefix, suffix) = hash.split_at(5);
+                let Some(text) = read_range(dir, prefix).await? else {
+                    return Ok(false);
+                };
+                Ok(text
+                    .lines()
+                    .map(|line| parse_line(line.trim()))
+                    .any(|(candidate, count)| candidate.eq_ignore_ascii_case(suffix) && count >= *min_count))
+            }
+        }
+    }
+}
+
+// `HASH:COUNT`, or a bare hash counted once
+fn parse_line(line: &str) -> (&str, u64) {
+    match line.split_once(':') {
+        Some((hash, count)) => (hash, count.trim().parse().unwrap_or(1)),
+        None => (line, 1),
+    }
+}
+
+// The range file for a prefix, with or without a `.txt` extension; None if the prefix has none
+async fn read_range(dir: &Path, prefix: &str) -> Result<Option<String>, String> {
+    for name in [format!("{}.txt", prefix), prefix.to_string()] {
+        match tokio::fs::read_to_string(dir.join(&name)).await {
+            Ok(text) => return Ok(Some(text)),
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(format!("Failed to read breached password range {}: {}", name, e)),
+        }
+    }
+    Ok(None)
+}
+
+/// The rules every new password must pass: at registration, on password change and on reset.
+pub struct PasswordPolicy {
+    min_length: usize,
+    min_score: u8,
+    breached: Option<BreachedPasswords>,
+}
+
+impl PasswordPolicy {
+    pub fn new(min_length: usize, min_score: u8, breached: Option<BreachedPasswords>) -> Self {
+        PasswordPolicy { min_length, min_score: min_score.min(4), breached }
+    }
+
+    /// Rejects `password` with a 400 naming every rule it breaks.
+    pub async fn check(&self, password: &str, username: &str, email: &str) -> Result<(), (StatusCode, String)> {
+        let mut problems = Vec::new();
+
+        let length = password.chars().count();
+        if length < self.min_length {
+            problems.push(format!("must be at least {} characters long", self.min_length));
+        }
+        if length > MAX_LENGTH {
+            problems.push(format!("must be at most {} characters long", MAX_LENGTH));
+        }
+
+        let lower = password.to_lowercase();
+        let username = username.trim().to_lowercase();
+        let email = email.trim().to_lowercase();
+        let local_part = email.split('@').next().unwrap_or_default();
+        if username.chars().count() >= 3 && lower.contains(&username) {
+            problems.push("must not contain your username".to_string());
+        }
+        if (email.chars().count() >= 3 && lower.contains(&email)) || (local_part.chars().count() >= 3 && lower.contains(local_part)) {
+            problems.push("must not contain your email address".to_string());
+        }
+
+        // Scoring a password that is far too long would only burn CPU
+        if length <= MAX_LENGTH {
+            let score = strength_score(password, &[&username, local_part]);
+            if score < self.min_score {
+                problems.push(format!(
+                    "is too easy to guess (strength {} of 4, at least {} required); use a longer passphrase and avoid common words, names, dates and keyboard patterns",
+                    score, self.min_score
+                ));
+            }
+        }
+
+        if let Some(breached) = &self.breached {
+            if breached.contains(password).await.map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))? {
+                problems.push("has appeared in a known data breach, so attackers will try it; choose a different one".to_string());
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err((StatusCode::BAD_REQUEST, format!("Password {}", problems.join("; "))))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Where a test keeps its breached-password file or range directory
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("bailbridge-breached-{}-{}", std::process::id(), name))
+    }
+
+    fn policy(breached: Option<BreachedPasswords>) -> PasswordPolicy {
+        PasswordPolicy::new(12, 3, breached)
+    }
+
+    async fn problems(policy: &PasswordPolicy, password: &str) -> String {
+        let (status, message) = policy.check(password, "jdoe", "jane.doe@example.com").await.unwrap_err();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        message
+    }
+
+    #[test]
+    fn common_patterns_score_low() {
+        for password in ["password", "P@ssw0rd", "drowssap", "qwertyuiop", "abcdefghij", "aaaaaaaaaa", "abcabcabcabc", "1234567890", "sunshine2024"] {
+            assert!(strength_score(password, &[]) <= 1, "{} scored {}", password, strength_score(password, &[]));
+        }
+    }
+
+    #[test]
+    fn long_unpredictable_passwords_score_high() {
+        for password in ["vK9#qT2!mZ7pWx", "tram-olive-quasar-fjord-81", "Xq7gR2vN9kLp"] {
+            assert_eq!(strength_score(password, &[]), 4, "{}", password);
+        }
+    }
+
+    #[test]
+    fn user_inputs_count_as_common_words() {
+        let password = "Zarvenkolm2031";
+        assert!(strength_score(password, &["zarvenkolm"]) < strength_score(password, &[]));
+    }
+
+    #[test]
+    fn parse_line_reads_counts() {
+        assert_eq!(parse_line("ABCDEF:12"), ("ABCDEF", 12));
+        assert_eq!(parse_line("ABCDEF: 3"), ("ABCDEF", 3));
+        assert_eq!(parse_line("ABCDEF:x"), ("ABCDEF", 1));
+        assert_eq!(parse_line("ABCDEF"), ("ABCDEF", 1));
+    }
+
+    #[tokio::test]
+    async fn accepts_a_strong_password() {
+        assert!(policy(None).check("tram-olive-quasar-fjord-81", "jdoe", "jane.doe@example.com").await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn names_each_broken_rule() {
+        let policy = policy(None);
+        assert!(problems(&policy, "Xq7gR2vN").await.contains("must be at least 12 characters long"));
+        assert!(problems(&policy, &"Xq7gR2vN9kLp".repeat(11)).await.contains("must be at most 128 characters long"));
+        assert!(problems(&policy, "Xq7gR2vN-JDOE-9kLp").await.contains("must not contain your username"));
+        assert!(problems(&policy, "Xq7gR2vN-jane.doe-9kLp").await.contains("must not contain your email address"));
+        assert!(problems(&policy, "passwordpassword").await.contains("is too easy to guess (strength 0 of 4, at least 3 required)"));
+    }
+
+    #[tokio::test]
+    async fn reports_every_problem_at_once() {
+        let message = problems(&policy(None), "jdoe123").await;
+        assert!(message.starts_with("Password must be at least 12 characters long; must not contain your username; is too easy to guess"), "{}", message);
+    }
+
+    #[tokio::test]
+    async fn refuses_breached_passwords_from_a_hash_file() {
+        let path = temp_path("hashes.txt");
+        let listed = sha1_hex("tram-olive-quasar-fjord-81");
+        let rare = sha1_hex("Xq7gR2vN9kLp-wide");
+        fs::write(&path, format!("{}:42\n\n{}:1\n", listed.to_lowercase(), rare)).unwrap();
+
+        let breached = BreachedPasswords::open(path.to_str().unwrap(), 2).unwrap();
+        let policy = policy(Some(breached));
+        assert!(problems(&policy, "tram-olive-quasar-fjord-81").await.contains("has appeared in a known data breach"));
+        assert!(policy.check("Xq7gR2vN9kLp-wide", "jdoe", "jane.doe@example.com").await.is_ok());
+
+        fs::write(&path, "not-a-hash:5\n").unwrap();
+        assert!(BreachedPasswords::open(path.to_str().unwrap(), 1).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn refuses_breached_passwords_from_a_range_directory() {
+        let dir = temp_path("ranges");
+        fs::create_dir_all(&dir).unwrap();
+        let hash = sha1_hex("tram-olive-quasar-fjord-81");
+        let (prefix, suffix) = hash.split_at(5);
+        fs::write(dir.join(format!("{}.txt", prefix)), format!("0000000000000000000000000000000000A:9\r\n{}:3\r\n", suffix)).unwrap();
+
+        let breached = BreachedPasswords::open(dir.to_str().unwrap(), 1).unwrap();
+        assert!(breached.contains("tram-olive-quasar-fjord-81").await.unwrap());
+        assert!(!breached.contains("Xq7gR2vN9kLp-wide").await.unwrap());
+
+        let strict = BreachedPasswords::open(dir.to_str().unwrap(), 5).unwrap();
+        assert!(!strict.contains("tram-olive-quasar-fjord-81").await.unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
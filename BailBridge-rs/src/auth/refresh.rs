@@ -0,0 +1,81 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+use uuid::Uuid;
+
+use crate::auth::tokens::{generate_token, hash_token};
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct RefreshToken {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub family_id: Uuid,
+    pub expires_at: DateTime<Utc>,
+    pub rotated_at: Option<DateTime<Utc>>,
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+/// Issues a new refresh token in `family_id` and returns it with its row id.
+pub async fn issue(conn: &mut sqlx::PgConnection, user_id: Uuid, family_id: Uuid, ttl_secs: i64) -> Result<(Uuid, String), (StatusCode, String)> {
+    let token = generate_token();
+    let id = Uuid::new_v4();
+
+    sqlx::query(
+        "INSERT INTO refresh_tokens (id, user_id, family_id, token_hash, expires_at) VALUES ($1, $2, $3, $4, $5)"
+    )
+    .bind(id)
+    .bind(user_id)
+    .bind(family_id)
+    .bind(hash_token(&token))
+    .bind(Utc::now() + Duration::seconds(ttl_secs))
+    .execute(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to issue refresh token: {}", e)))?;
+
+    Ok((id, token))
+}
+
+// A family is one login session, so revoking it also ends the session and the access tokens issued under it
+pub async fn revoke_family(conn: &mut sqlx::PgConnection, family_id: Uuid) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE family_id = $1 AND revoked_at IS NULL")
+        .bind(family_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to revoke refresh tokens: {}", e)))?;
+    sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE id = $1 AND revoked_at IS NULL")
+        .bind(family_id)
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end session: {}", e)))?;
+    Ok(())
+}
+
+pub async fn revoke_all_for_user(conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to revoke refresh tokens: {}", e)))?;
+    sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND revoked_at IS NULL")
+        .bind(user_id)
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end sessions: {}", e)))?;
+    Ok(())
+}
+
+// Every session but the one in use, e.g. after a password change
+pub async fn revoke_all_except(conn: &mut sqlx::PgConnection, user_id: Uuid, family_id: Uuid) -> Result<(), (StatusCode, String)> {
+    sqlx::query("UPDATE refresh_tokens SET revoked_at = NOW() WHERE user_id = $1 AND family_id <> $2 AND revoked_at IS NULL")
+        .bind(user_id)
+        .bind(family_id)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to revoke refresh tokens: {}", e)))?;
+    sqlx::query("UPDATE sessions SET revoked_at = NOW() WHERE user_id = $1 AND id <> $2 AND revoked_at IS NULL")
+        .bind(user_id)
+        .bind(family_id)
+        .execute(conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to end sessions: {}", e)))?;
+    Ok(())
+}
@@ -0,0 +1,157 @@
+use std::{collections::HashMap, time::{Duration, Instant}};
+
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
+use uuid::Uuid;
+
+use crate::{auth::jwt::Claims, config::Config, db::DbPool};
+
+// Rows are stamped with their transaction's start time, so re-read a little behind
+// the last sync to catch revocations that committed late
+const SYNC_OVERLAP_SECS: i64 = 60;
+
+#[derive(sqlx::FromRow)]
+struct RevocationRow {
+    jti: Option<Uuid>,
+    user_id: Uuid,
+    revoked_at: DateTime<Utc>,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+struct Cache {
+    synced_from: Option<DateTime<Utc>>,
+    synced_at: Option<Instant>,
+    tokens: HashMap<Uuid, DateTime<Utc>>, // jti -> token expiry
+    users: HashMap<Uuid, (DateTime<Utc>, DateTime<Utc>)>, // user -> (revoked before, entry expiry)
+}
+
+impl Cache {
+    fn apply(&mut self, row: &RevocationRow) {
+        match row.jti {
+            Some(jti) => {
+                self.tokens.insert(jti, row.expires_at);
+            }
+            None => {
+                let entry = self.users.entry(row.user_id).or_insert((row.revoked_at, row.expires_at));
+                if row.revoked_at >= entry.0 {
+                    *entry = (row.revoked_at, row.expires_at);
+                }
+            }
+        }
+    }
+
+    fn prune(&mut self, now: DateTime<Utc>) {
+        self.tokens.retain(|_, expires_at| *expires_at > now);
+        self.users.retain(|_, (_, expires_at)| *expires_at > now);
+    }
+}
+
+/// Revoked access tokens, persisted in `revoked_tokens` and mirrored in memory so the
+/// token extractor doesn't query the table on every request. The mirror pulls rows
+/// written by other servers every `REVOCATION_SYNC_INTERVAL_SECS`.
+pub struct RevocationStore {
+    db: DbPool,
+    sync_interval: Duration,
+    access_token_ttl_secs: i64,
+    cache: RwLock<Cache>,
+}
+
+impl RevocationStore {
+    pub fn new(db: DbPool, config: &Config) -> Self {
+        RevocationStore {
+            db,
+            sync_interval: Duration::from_secs(config.revocation_sync_interval_secs),
+            access_token_ttl_secs: config.access_token_ttl_secs,
+            cache: RwLock::new(Cache::default()),
+        }
+    }
+
+    async fn sync(&self) -> Result<(), String> {
+        let is_fresh = |cache: &Cache| cache.synced_at.is_some_and(|at| at.elapsed() < self.sync_interval);
+
+        if is_fresh(&*self.cache.read().await) {
+            return Ok(());
+        }
+
+        let mut cache = self.cache.write().await;
+        if is_fresh(&cache) {
+            return Ok(());
+        }
+
+        let started = Utc::now();
+        let rows = sqlx::query_as::<_, RevocationRow>(
+            r#"
+            SELECT jti, user_id, revoked_at, expires_at FROM revoked_tokens
+            WHERE ($1::timestamptz IS NULL OR revoked_at >= $1) AND expires_at > NOW()
+            "#
+        )
+        .bind(cache.synced_from)
+        .fetch_all(&self.db)
+        .await
+        .map_err(|e| format!("Failed to load revoked tokens: {}", e))?;
+
+        for row in &rows {
+            cache.apply(row);
+        }
+        cache.prune(started);
+        cache.synced_from = Some(started - chrono::Duration::seconds(SYNC_OVERLAP_SECS));
+        cache.synced_at = Some(Instant::now());
+
+        Ok(())
+    }
+
+    pub async fn is_revoked(&self, claims: &Claims) -> Result<bool, String> {
+        self.sync().await?;
+
+        let jti = Uuid::parse_str(&claims.jti).map_err(|_| "Invalid token ID".to_string())?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| "Invalid user ID".to_string())?;
+
+        let cache = self.cache.read().await;
+        if cache.tokens.contains_key(&jti) {
+            return Ok(true);
+        }
+        // `iat` only has whole seconds, so a token from the same second as the revocation goes too
+        Ok(cache
+            .users
+            .get(&user_id)
+            .is_some_and(|(revoked_before, _)| (claims.iat as i64) <= revoked_before.timestamp()))
+    }
+
+    /// Revokes one access token, e.g. on logout.
+    pub async fn revoke_token(&self, conn: &mut sqlx::PgConnection, claims: &Claims) -> Result<(), String> {
+        let jti = Uuid::parse_str(&claims.jti).map_err(|_| "Invalid token ID".to_string())?;
+        let user_id = Uuid::parse_str(&claims.sub).map_err(|_| "Invalid user ID".to_string())?;
+        let expires_at = DateTime::from_timestamp(claims.exp as i64, 0).unwrap_or_else(Utc::now);
+
+        self.insert(conn, Some(jti), user_id, expires_at).await
+    }
+
+    /// Revokes every access token issued to `user_id` so far.
+    pub async fn revoke_user(&self, conn: &mut sqlx::PgConnection, user_id: Uuid) -> Result<(), String> {
+        // No token issued before now can outlive the access token lifetime
+        let expires_at = Utc::now() + chrono::Duration::seconds(self.access_token_ttl_secs);
+
+        self.insert(conn, None, user_id, expires_at).await
+    }
+
+    async fn insert(&self, conn: &mut sqlx::PgConnection, jti: Option<Uuid>, user_id: Uuid, expires_at: DateTime<Utc>) -> Result<(), String> {
+        let row = sqlx::query_as::<_, RevocationRow>(
+            r#"
+            INSERT INTO revoked_tokens (jti, user_id, expires_at) VALUES ($1, $2, $3)
+            RETURNING jti, user_id, revoked_at, expires_at
+            "#
+        )
+        .bind(jti)
+        .bind(user_id)
+        .bind(expires_at)
+        .fetch_one(conn)
+        .await
+        .map_err(|e| format!("Failed to revoke token: {}", e))?;
+
+        // Take effect on this server right away; other servers catch up on their next sync
+        self.cache.write().await.apply(&row);
+
+        Ok(())
+    }
+}
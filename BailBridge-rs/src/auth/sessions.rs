@@ -0,0 +1,92 @@
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use crate::{audit::middlewares::ClientInfo, auth::jwt::Claims, db::DbPool};
+
+// Activity is written back at most this often per session, not on every request
+const LAST_SEEN_INTERVAL_SECS: f64 = 60.0;
+
+#[derive(sqlx::FromRow)]
+struct SessionState {
+    revoked: bool,
+    stale: bool,
+}
+
+/// Records the session a login starts. Returns true when it comes from a device
+/// (user agent) the user has not logged in from before, not counting their first login.
+pub async fn start(conn: &mut sqlx::PgConnection, session_id: Uuid, user_id: Uuid, client: &ClientInfo) -> Result<bool, (StatusCode, String)> {
+    let (has_sessions, known_device): (bool, bool) = sqlx::query_as(
+        "SELECT COUNT(*) > 0, COUNT(*) FILTER (WHERE user_agent IS NOT DISTINCT FROM $2) > 0 FROM sessions WHERE user_id = $1"
+    )
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .fetch_one(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sessions: {}", e)))?;
+
+    sqlx::query(
+        "INSERT INTO sessions (id, user_id, user_agent, ip_address, last_seen_ip) VALUES ($1, $2, $3, $4, $4)"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .execute(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create session: {}", e)))?;
+
+    Ok(has_sessions && !known_device)
+}
+
+/// Marks a session active on token refresh. Logins from before sessions were
+/// tracked get their row here.
+pub async fn resume(conn: &mut sqlx::PgConnection, session_id: Uuid, user_id: Uuid, client: &ClientInfo) -> Result<(), (StatusCode, String)> {
+    sqlx::query(
+        r#"
+        INSERT INTO sessions (id, user_id, user_agent, ip_address, last_seen_ip) VALUES ($1, $2, $3, $4, $4)
+        ON CONFLICT (id) DO UPDATE SET last_seen_at = NOW(), last_seen_ip = EXCLUDED.last_seen_ip
+        "#
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(&client.user_agent)
+    .bind(&client.ip_address)
+    .execute(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update session: {}", e)))?;
+    Ok(())
+}
+
+/// Rejects access tokens whose session was revoked, and keeps `last_seen_at` current.
+pub async fn check(db: &DbPool, claims: &Claims, ip_address: Option<&str>) -> Result<(), (StatusCode, String)> {
+    let ended = || (StatusCode::UNAUTHORIZED, "Session has ended, please log in again".to_string());
+
+    let session_id = Uuid::parse_str(&claims.sid).map_err(|_| ended())?;
+    let user_id = Uuid::parse_str(&claims.sub).map_err(|_| ended())?;
+
+    let session = sqlx::query_as::<_, SessionState>(
+        "SELECT revoked_at IS NOT NULL AS revoked, last_seen_at < NOW() - make_interval(secs => $3) AS stale FROM sessions WHERE id = $1 AND user_id = $2"
+    )
+    .bind(session_id)
+    .bind(user_id)
+    .bind(LAST_SEEN_INTERVAL_SECS)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch session: {}", e)))?
+    .ok_or_else(ended)?;
+
+    if session.revoked {
+        return Err(ended());
+    }
+
+    if session.stale {
+        sqlx::query("UPDATE sessions SET last_seen_at = NOW(), last_seen_ip = $2 WHERE id = $1")
+            .bind(session_id)
+            .bind(ip_address)
+            .execute(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update session: {}", e)))?;
+    }
+
+    Ok(())
+}
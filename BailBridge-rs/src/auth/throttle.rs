@@ -0,0 +1,117 @@
+use axum::http::StatusCode;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{config::Config, db::DbPool};
+
+/// Throttle key for failed logins against one email address, whether or not it has an account.
+pub fn account_key(email: &str) -> String {
+    format!("account:{}", email.trim().to_lowercase())
+}
+
+/// Throttle key for failed logins from one client address.
+pub fn ip_key(ip_address: &str) -> String {
+    format!("ip:{}", ip_address)
+}
+
+/// Rejects the attempt while any of `keys` is locked out.
+pub async fn check(db: &DbPool, keys: &[String]) -> Result<(), (StatusCode, String)> {
+    let locked_until: Option<DateTime<Utc>> = sqlx::query_scalar(
+        "SELECT MAX(locked_until) FROM login_throttles WHERE key = ANY($1) AND locked_until > NOW()"
+    )
+    .bind(keys)
+    .fetch_one(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check login throttle: {}", e)))?;
+
+    match locked_until {
+        Some(until) => {
+            let wait = (until - Utc::now()).num_seconds().max(1);
+            Err((StatusCode::TOO_MANY_REQUESTS, format!("Too many failed login attempts, try again in {} seconds", wait)))
+        }
+        None => Ok(()),
+    }
+}
+
+// The lockout after `failures` failures: `base_secs` at the limit, doubling with each one past it
+fn lockout_secs(failures: i32, max_failures: i32, base_secs: i64, max_secs: i64) -> Option<i64> {
+    if failures < max_failures {
+        return None;
+    }
+    let doublings = (failures - max_failures).min(30) as u32;
+    Some(base_secs.saturating_mul(1i64 << doublings).min(max_secs))
+}
+
+/// Counts a failed attempt against `key`. Once `max_failures` is reached within the
+/// failure window the key is locked, for twice as long with each further failure.
+pub async fn record_failure(db: &DbPool, config: &Config, key: &str, max_failures: i32) -> Result<(), (StatusCode, String)> {
+    // The window restarts once both the last failure and any lockout are long past
+    let failures: i32 = sqlx::query_scalar(
+        "INSERT INTO login_throttles (key, failures, last_failure_at) VALUES ($1, 1, NOW())
+         ON CONFLICT (key) DO UPDATE SET
+             failures = CASE
+                 WHEN GREATEST(login_throttles.last_failure_at, login_throttles.locked_until) < NOW() - make_interval(secs => $2)
+                 THEN 1
+                 ELSE login_throttles.failures + 1
+             END,
+             last_failure_at = NOW()
+         RETURNING failures"
+    )
+    .bind(key)
+    .bind(config.login_failure_window_secs as f64)
+    .fetch_one(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record login failure: {}", e)))?;
+
+    if let Some(lockout_secs) = lockout_secs(failures, max_failures, config.login_lockout_base_secs, config.login_lockout_max_secs) {
+        sqlx::query("UPDATE login_throttles SET locked_until = $1 WHERE key = $2")
+            .bind(Utc::now() + Duration::seconds(lockout_secs))
+            .bind(key)
+            .execute(db)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to lock out login: {}", e)))?;
+    }
+
+    Ok(())
+}
+
+/// Forgets failures and lifts any lockout on `key`.
+pub async fn clear<'e>(executor: impl sqlx::PgExecutor<'e>, key: &str) -> Result<(), (StatusCode, String)> {
+    sqlx::query("DELETE FROM login_throttles WHERE key = $1")
+        .bind(key)
+        .execute(executor)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to clear login throttle: {}", e)))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_lockout_below_the_limit() {
+        assert_eq!(lockout_secs(0, 5, 60, 3600), None);
+        assert_eq!(lockout_secs(4, 5, 60, 3600), None);
+    }
+
+    #[test]
+    fn lockout_doubles_past_the_limit() {
+        assert_eq!(lockout_secs(5, 5, 60, 3600), Some(60));
+        assert_eq!(lockout_secs(6, 5, 60, 3600), Some(120));
+        assert_eq!(lockout_secs(7, 5, 60, 3600), Some(240));
+        assert_eq!(lockout_secs(10, 5, 60, 3600), Some(1920));
+    }
+
+    #[test]
+    fn lockout_is_capped() {
+        assert_eq!(lockout_secs(11, 5, 60, 3600), Some(3600));
+        assert_eq!(lockout_secs(1000, 5, 60, 3600), Some(3600));
+        assert_eq!(lockout_secs(i32::MAX, 1, i64::MAX / 2, i64::MAX), Some(i64::MAX));
+    }
+
+    #[test]
+    fn keys_are_normalised() {
+        assert_eq!(account_key("  Jane@Example.com "), "account:jane@example.com");
+        assert_eq!(ip_key("203.0.113.7"), "ip:203.0.113.7");
+    }
+}
@@ -0,0 +1,14 @@
+use password_hash::rand_core::{OsRng, RngCore};
+use sha2::{Digest, Sha256};
+
+/// A random opaque token for refresh, reset and verification links.
+pub fn generate_token() -> String {
+    let mut secret = [0u8; 32];
+    OsRng.fill_bytes(&mut secret);
+    hex::encode(secret)
+}
+
+/// Opaque tokens are stored only as their SHA-256, so a database leak doesn't hand them out.
+pub fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
@@ -0,0 +1,146 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use password_hash::rand_core::{OsRng, RngCore};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+const STEP_SECS: u64 = 30;
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// A fresh 160-bit secret, base32-encoded as authenticator apps expect.
+pub fn new_secret() -> String {
+    let mut bytes = [0u8; 20];
+    OsRng.fill_bytes(&mut bytes);
+    Secret::Raw(bytes.to_vec()).to_encoded().to_string()
+}
+
+// RFC 6238 defaults: SHA-1, 6 digits, 30-second steps. Skew is handled in `verify`.
+fn totp(secret: &str, issuer: &str, account: &str) -> Result<TOTP, String> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| format!("Invalid TOTP secret: {}", e))?;
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        STEP_SECS,
+        bytes,
+        Some(issuer.replace(':', "")),
+        account.replace(':', ""),
+    )
+    .map_err(|e| format!("Invalid TOTP parameters: {}", e))
+}
+
+/// The `otpauth://` URI to render as a QR code for authenticator apps.
+pub fn provisioning_uri(secret: &str, issuer: &str, account: &str) -> Result<String, String> {
+    Ok(totp(secret, issuer, account)?.get_url())
+}
+
+/// Checks `code` against the current step and one step either side, returning the
+/// matched step. Steps at or before `last_used_step` are refused so a code can't be replayed.
+pub fn verify(secret: &str, code: &str, last_used_step: Option<i64>) -> Result<Option<i64>, String> {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| format!("System clock error: {}", e))?
+        .as_secs();
+    verify_at(secret, code, last_used_step, now)
+}
+
+fn verify_at(secret: &str, code: &str, last_used_step: Option<i64>, now: u64) -> Result<Option<i64>, String> {
+    let totp = totp(secret, "BailBridge", "")?;
+    let current = (now / STEP_SECS) as i64;
+    let code = code.trim();
+
+    Ok((current - 1..=current + 1)
+        .filter(|step| last_used_step.is_none_or(|last| *step > last))
+        .find(|step| totp.check(code, *step as u64 * STEP_SECS)))
+}
+
+/// One-time recovery codes like `3f9a1-c07be`, shown to the user once.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODE_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            OsRng.fill_bytes(&mut bytes);
+            let code = hex::encode(bytes);
+            format!("{}-{}", &code[..5], &code[5..])
+        })
+        .collect()
+}
+
+/// Recovery codes are compared case-insensitively and with or without the dash.
+pub fn normalize_recovery_code(code: &str) -> String {
+    let code: String = code.trim().to_lowercase().chars().filter(|c| c.is_ascii_alphanumeric()).collect();
+    if code.len() == 10 {
+        format!("{}-{}", &code[..5], &code[5..])
+    } else {
+        code
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The RFC 6238 SHA-1 test key, "12345678901234567890"
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+    const NOW: u64 = 1_111_111_109;
+
+    fn code_at(time: u64) -> String {
+        totp(SECRET, "BailBridge", "").unwrap().generate(time)
+    }
+
+    #[test]
+    fn matches_the_rfc_test_vector() {
+        // 94287082 at T = 59 in the RFC, truncated to six digits
+        assert_eq!(code_at(59), "287082");
+        assert_eq!(verify_at(SECRET, "287082", None, 59).unwrap(), Some(1));
+    }
+
+    #[test]
+    fn accepts_one_step_either_side() {
+        let step = (NOW / STEP_SECS) as i64;
+        assert_eq!(verify_at(SECRET, &code_at(NOW), None, NOW).unwrap(), Some(step));
+        assert_eq!(verify_at(SECRET, &code_at(NOW - STEP_SECS), None, NOW).unwrap(), Some(step - 1));
+        assert_eq!(verify_at(SECRET, &code_at(NOW + STEP_SECS), None, NOW).unwrap(), Some(step + 1));
+        assert_eq!(verify_at(SECRET, &code_at(NOW - 2 * STEP_SECS), None, NOW).unwrap(), None);
+        assert_eq!(verify_at(SECRET, &code_at(NOW + 2 * STEP_SECS), None, NOW).unwrap(), None);
+    }
+
+    #[test]
+    fn refuses_replayed_steps() {
+        let step = (NOW / STEP_SECS) as i64;
+        let code = code_at(NOW);
+        assert_eq!(verify_at(SECRET, &code, Some(step), NOW).unwrap(), None);
+        assert_eq!(verify_at(SECRET, &code, Some(step + 1), NOW).unwrap(), None);
+        assert_eq!(verify_at(SECRET, &code, Some(step - 1), NOW).unwrap(), Some(step));
+
+        // An older code is no good once a newer one has been used
+        assert_eq!(verify_at(SECRET, &code_at(NOW - STEP_SECS), Some(step), NOW).unwrap(), None);
+    }
+
+    #[test]
+    fn trims_the_code_and_rejects_junk() {
+        assert!(verify_at(SECRET, &format!(" {} ", code_at(NOW)), None, NOW).unwrap().is_some());
+        assert_eq!(verify_at(SECRET, "abcdef", None, NOW).unwrap(), None);
+        assert_eq!(verify_at(SECRET, "", None, NOW).unwrap(), None);
+        assert!(verify_at("not base32!", "123456", None, NOW).is_err());
+    }
+
+    #[test]
+    fn new_secrets_work_with_authenticators() {
+        let secret = new_secret();
+        assert_eq!(secret.len(), 32);
+        assert!(provisioning_uri(&secret, "BailBridge", "a@x.com").unwrap().starts_with("otpauth://totp/BailBridge:"));
+    }
+
+    #[test]
+    fn recovery_codes_normalize_to_the_issued_form() {
+        for code in generate_recovery_codes() {
+            assert_eq!(normalize_recovery_code(&code), code);
+            assert_eq!(normalize_recovery_code(&code.to_uppercase().replace('-', "")), code);
+        }
+        assert_eq!(normalize_recovery_code(" 3F9A1-C07BE\n"), "3f9a1-c07be");
+        assert_eq!(normalize_recovery_code("3f9a1 c07be"), "3f9a1-c07be");
+        assert_eq!(normalize_recovery_code("3f9a1-c07"), "3f9a1c07");
+    }
+}
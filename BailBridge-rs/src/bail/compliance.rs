@@ -0,0 +1,58 @@
+use std::time::Duration;
+
+use crate::db::DbPool;
+
+// Flags every fully elapsed reporting period of an active `report_to_police`
+// condition that has no verified check-in. Periods are counted from the condition's
+// start date; `once` conditions have no recurring period and are skipped.
+const FLAG_MISSED_CHECK_INS: &str = r#"
+    INSERT INTO bail_violations (id, condition_id, application_id, period_start, period_end)
+    SELECT gen_random_uuid(), c.id, c.application_id, p.period_start, p.period_end
+    FROM bail_conditions c
+    JOIN bail_applications a ON a.id = c.application_id
+    CROSS JOIN LATERAL (
+        SELECT CASE c.frequency
+            WHEN 'daily' THEN INTERVAL '1 day'
+            WHEN 'weekly' THEN INTERVAL '7 days'
+            WHEN 'fortnightly' THEN INTERVAL '14 days'
+            WHEN 'monthly' THEN INTERVAL '1 month'
+        END AS step
+    ) f
+    CROSS JOIN LATERAL (
+        SELECT gs::date AS period_start, (gs + f.step)::date AS period_end
+        FROM generate_series(
+            c.start_date::timestamp,
+            LEAST(COALESCE(c.end_date + 1, CURRENT_DATE), CURRENT_DATE)::timestamp - f.step,
+            f.step
+        ) gs
+    ) p
+    WHERE c.condition_type = 'report_to_police'
+      AND f.step IS NOT NULL
+      AND a.status = 'approved'
+      AND NOT EXISTS (
+          SELECT 1 FROM bail_check_ins ci
+          WHERE ci.condition_id = c.id
+            AND ci.verified
+            AND ci.checked_in_at::date >= p.period_start
+            AND ci.checked_in_at::date < p.period_end
+      )
+    ON CONFLICT (condition_id, period_start) DO NOTHING
+"#;
+
+pub async fn flag_missed_check_ins(db: &DbPool) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query(FLAG_MISSED_CHECK_INS).execute(db).await?;
+    Ok(result.rows_affected())
+}
+
+/// Background job that periodically marks missed check-ins as violations.
+pub async fn run_violation_scanner(db: DbPool, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        ticker.tick().await;
+        match flag_missed_check_ins(&db).await {
+            Ok(0) => {}
+            Ok(count) => println!("Compliance scan flagged {} missed check-in(s)", count),
+            Err(e) => eprintln!("Compliance scan failed: {}", e),
+        }
+    }
+}
@@ -1,13 +1,22 @@
-use axum::{Json, extract::{State, Path}, http::StatusCode};
+use axum::{Json, extract::{State, Path, Query}, http::StatusCode};
 use uuid::Uuid;
 use chrono::Utc;
+use validator::Validate;
 
 use crate::{
     db::DbPool,
     bail::models::{
         BailApplication, CreateBailApplication, 
-        BailApplicationResponse, BailApplicationSummary
+        BailApplicationResponse, BailApplicationSummary, TransitionRequest,
+        BailApplicationDetail, BailOrder, DecideBailApplication, Decision,
+        BailCondition, CreateBailCondition, ConditionType, BailConditionQuery,
+        BailConditionSearchResult, BailCheckIn, CreateCheckIn, BailViolation,
+        ComplianceEvent, BailApplicationDraft, BailApplicationPatch, ApplicationRevision
     },
+    bail::revisions::record_revisions,
+    hearings::models::Hearing,
+    sureties::models::Surety,
+    bail::status::ApplicationStatus,
     auth::jwt::Claims,
 };
 
@@ -20,10 +29,204 @@ fn generate_application_number() -> String {
     format!("BAIL-{}-{}", timestamp, random_suffix)
 }
 
+// Drafts are private to their owner and only reachable through fetch_draft
+pub(crate) async fn fetch_application(db: &DbPool, application_number: &str) -> Result<BailApplication, (StatusCode, String)> {
+    sqlx::query_as::<_, BailApplication>(
+        "SELECT * FROM bail_applications WHERE application_number = $1 AND status <> 'draft'"
+    )
+    .bind(application_number)
+    .fetch_one(db)
+    .await
+    .map_err(|_| (StatusCode::NOT_FOUND, "Bail application not found".to_string()))
+}
+
+async fn fetch_draft(db: &DbPool, application_number: &str, claims: &Claims) -> Result<BailApplicationDraft, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    sqlx::query_as::<_, BailApplicationDraft>(
+        "SELECT * FROM bail_applications WHERE application_number = $1 AND user_id = $2 AND status = 'draft'"
+    )
+    .bind(application_number)
+    .bind(user_id)
+    .fetch_optional(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch draft: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Draft not found".to_string()))
+}
+
+fn validate_submission(payload: &CreateBailApplication) -> Result<(), (StatusCode, String)> {
+    payload.validate()
+        .map_err(|e| (StatusCode::UNPROCESSABLE_ENTITY, format!("Invalid application: {}", e)))
+}
+
+/// Authorization check: user can only view their own, other roles can view all.
+/// Returns the caller's user ID.
+pub(crate) fn ensure_can_view(application: &BailApplication, claims: &Claims) -> Result<Uuid, (StatusCode, String)> {
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    if claims.role == "user" && application.user_id != user_id {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+    Ok(user_id)
+}
+
+// Users act on their own applications; lawyers and judges on unassigned cases or cases assigned to them
+fn ensure_participant(application: &BailApplication, claims: &Claims, actor_id: Uuid) -> Result<(), (StatusCode, String)> {
+    let allowed = match claims.role.as_str() {
+        "user" => application.user_id == actor_id,
+        "lawyer" => application.assigned_lawyer_id.is_none_or(|id| id == actor_id),
+        "judge" => application.judge_id.is_none_or(|id| id == actor_id),
+        _ => false,
+    };
+
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+    Ok(())
+}
+
+fn validate_order(order: &DecideBailApplication) -> Result<(), (StatusCode, String)> {
+    if order.order_text.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "An order needs its text".to_string()));
+    }
+
+    if order.bail_amount.is_some_and(|amount| amount < 0) {
+        return Err((StatusCode::BAD_REQUEST, "Bail amount cannot be negative".to_string()));
+    }
+
+    match order.decision {
+        Decision::Rejected if order.bail_amount.is_some() || !order.conditions.is_empty() => {
+            return Err((StatusCode::BAD_REQUEST, "A rejected order cannot set a bail amount or conditions".to_string()));
+        }
+        Decision::Granted if order.bail_amount.is_none() => {
+            return Err((StatusCode::BAD_REQUEST, "A granted order needs a bail amount".to_string()));
+        }
+        _ => {}
+    }
+
+    for condition in &order.conditions {
+        validate_condition(condition)?;
+    }
+
+    Ok(())
+}
+
+fn validate_condition(condition: &CreateBailCondition) -> Result<(), (StatusCode, String)> {
+    if condition.condition_type == ConditionType::ReportToPolice && condition.frequency.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "A reporting condition needs a frequency".to_string()));
+    }
+
+    if condition.end_date.is_some_and(|end| end < condition.start_date) {
+        return Err((StatusCode::BAD_REQUEST, "Condition end date is before its start date".to_string()));
+    }
+
+    Ok(())
+}
+
+/// Moves an application to `to` after checking the transition table.
+/// All status changes in this module must go through here.
+async fn transition_application( conn: &mut sqlx::PgConnection, application: &BailApplication, to: ApplicationStatus, claims: &Claims, ) -> Result<BailApplication, (StatusCode, String)> {
+
+    let actor_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    application.status.check_transition(to, &claims.role)?;
+    ensure_participant(application, claims, actor_id)?;
+
+    // A lawyer taking up a pending case becomes its assigned lawyer
+    let assigned_lawyer_id = if application.status == ApplicationStatus::Pending && to == ApplicationStatus::UnderReview {
+        Some(actor_id)
+    } else {
+        application.assigned_lawyer_id
+    };
+
+    // A judge listing a case for hearing takes it up
+    let judge_id = if application.status == ApplicationStatus::UnderReview && to == ApplicationStatus::ListedForHearing {
+        Some(actor_id)
+    } else {
+        application.judge_id
+    };
+
+    // Guard on the current status so concurrent moves cannot both succeed
+    let updated = sqlx::query_as::<_, BailApplication>(
+        r#"
+        UPDATE bail_applications
+        SET status = $1, assigned_lawyer_id = $2, judge_id = $3
+        WHERE id = $4 AND status = $5
+        RETURNING *
+        "#
+    )
+    .bind(to)
+    .bind(assigned_lawyer_id)
+    .bind(judge_id)
+    .bind(application.id)
+    .bind(application.status)
+    .fetch_optional(&mut *conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update status: {}", e)))?
+    .ok_or((StatusCode::CONFLICT, "Application status changed concurrently, please retry".to_string()))?;
+
+    record_revisions(conn, application, &updated, claims).await?;
+
+    Ok(updated)
+}
+
+/// Overwrites every case field of an application that is still in `expected_status`.
+async fn write_application_fields( conn: &mut sqlx::PgConnection, application_id: Uuid, payload: &CreateBailApplication, expected_status: ApplicationStatus, status: ApplicationStatus, ) -> Result<Option<BailApplication>, (StatusCode, String)> {
+    sqlx::query_as::<_, BailApplication>(
+        r#"
+        UPDATE bail_applications SET
+            applicant_name = $1, father_husband_name = $2, age = $3, gender = $4, address = $5,
+            phone_number = $6, email = $7,
+            fir_number = $8, police_station = $9, district = $10, state = $11, date_of_arrest = $12,
+            sections_applied = $13, case_description = $14,
+            bail_type = $15, previous_bail_applications = $16, previous_bail_details = $17,
+            surety_details = $18, medical_condition = $19, family_dependents = $20, employment_details = $21,
+            status = $22,
+            submitted_at = COALESCE(submitted_at, CASE WHEN $22 <> 'draft' THEN CURRENT_TIMESTAMP END)
+        WHERE id = $23 AND status = $24
+        RETURNING *
+        "#
+    )
+    .bind(&payload.applicant_name)
+    .bind(&payload.father_husband_name)
+    .bind(payload.age)
+    .bind(&payload.gender)
+    .bind(&payload.address)
+    .bind(&payload.phone_number)
+    .bind(&payload.email)
+    .bind(&payload.fir_number)
+    .bind(&payload.police_station)
+    .bind(&payload.district)
+    .bind(&payload.state)
+    .bind(payload.date_of_arrest)
+    .bind(&payload.sections_applied)
+    .bind(&payload.case_description)
+    .bind(&payload.bail_type)
+    .bind(payload.previous_bail_applications)
+    .bind(&payload.previous_bail_details)
+    .bind(&payload.surety_details)
+    .bind(&payload.medical_condition)
+    .bind(&payload.family_dependents)
+    .bind(&payload.employment_details)
+    .bind(status)
+    .bind(application_id)
+    .bind(expected_status)
+    .fetch_optional(conn)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update application: {}", e)))
+}
+
 
 
 pub async fn create_bail_application( State(db): State<DbPool>, claims: Claims, Json(payload): Json<CreateBailApplication>, ) -> Result<Json<BailApplicationResponse>, (StatusCode, String)> {
     
+    // Submitting is the move out of draft
+    ApplicationStatus::Draft.check_transition(ApplicationStatus::Pending, &claims.role)?;
+    validate_submission(&payload)?;
+
     let application_id = Uuid::new_v4();
     let application_number = generate_application_number();
     let user_id = Uuid::parse_str(&claims.sub)
@@ -70,7 +273,7 @@ pub async fn create_bail_application( State(db): State<DbPool>, claims: Claims,
     .bind(&payload.medical_condition)
     .bind(&payload.family_dependents)
     .bind(&payload.employment_details)
-    .bind("pending")
+    .bind(ApplicationStatus::Pending)
     .bind(Utc::now())
     .fetch_one(&db)
     .await
@@ -86,25 +289,49 @@ pub async fn create_bail_application( State(db): State<DbPool>, claims: Claims,
 
 
 
-pub async fn get_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>,  claims: Claims, ) -> Result<Json<BailApplication>, (StatusCode, String)> {
+pub async fn get_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>,  claims: Claims, ) -> Result<Json<BailApplicationDetail>, (StatusCode, String)> {
     
-    let application = sqlx::query_as::<_, BailApplication>(
-        "SELECT * FROM bail_applications WHERE application_number = $1"
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let order = sqlx::query_as::<_, BailOrder>(
+        "SELECT * FROM bail_orders WHERE application_id = $1 ORDER BY created_at DESC LIMIT 1"
     )
-    .bind(&application_number)
-    .fetch_one(&db)
+    .bind(application.id)
+    .fetch_optional(&db)
     .await
-    .map_err(|_| (StatusCode::NOT_FOUND, "Bail application not found".to_string()))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch bail order: {}", e)))?;
 
-    // Authorization check: user can only view their own, lawyers and judges can view all
-    let user_id = Uuid::parse_str(&claims.sub)
-        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
-    
-    if claims.role == "user" && application.user_id != user_id {
-        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
-    }
+    let conditions = sqlx::query_as::<_, BailCondition>(
+        "SELECT * FROM bail_conditions WHERE application_id = $1 ORDER BY created_at"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch bail conditions: {}", e)))?;
+
+    let upcoming_hearings = sqlx::query_as::<_, Hearing>(
+        r#"
+        SELECT * FROM hearings
+        WHERE application_id = $1 AND status = 'scheduled' AND scheduled_at >= $2
+        ORDER BY scheduled_at
+        "#
+    )
+    .bind(application.id)
+    .bind(Utc::now())
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch hearings: {}", e)))?;
 
-    Ok(Json(application))
+    let sureties = sqlx::query_as::<_, Surety>(
+        "SELECT * FROM sureties WHERE application_id = $1 ORDER BY created_at"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sureties: {}", e)))?;
+
+    Ok(Json(BailApplicationDetail { application, order, conditions, upcoming_hearings, sureties }))
 }
 
 
@@ -118,7 +345,7 @@ pub async fn get_my_bail_applications( State(db): State<DbPool>, claims: Claims,
         r#"
         SELECT id, application_number, applicant_name, fir_number, status, bail_type, created_at
         FROM bail_applications
-        WHERE user_id = $1
+        WHERE user_id = $1 AND status <> 'draft'
         ORDER BY created_at DESC
         "#
     )
@@ -142,6 +369,7 @@ pub async fn get_all_bail_applications_for_lawyer( State(db): State<DbPool>, cla
         r#"
         SELECT id, application_number, applicant_name, fir_number, status, bail_type, created_at
         FROM bail_applications
+        WHERE status <> 'draft'
         ORDER BY created_at DESC
         "#
     )
@@ -160,22 +388,522 @@ pub async fn assign_lawyer_to_case( State(db): State<DbPool>, Path(application_n
         return Err((StatusCode::FORBIDDEN, "Only lawyers can assign cases".to_string()));
     }
 
-    let lawyer_id = Uuid::parse_str(&claims.sub)
+    let application = fetch_application(&db, &application_number).await?;
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+    let result = transition_application(&mut tx, &application, ApplicationStatus::UnderReview, &claims).await?;
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit status change: {}", e)))?;
+
+    Ok(Json(BailApplicationResponse {
+        id: result.id,
+        application_number: result.application_number,
+        status: result.status,
+        created_at: result.created_at,
+    }))
+}
+
+
+
+pub async fn transition_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<TransitionRequest>, ) -> Result<Json<BailApplicationResponse>, (StatusCode, String)> {
+
+    if matches!(payload.to, ApplicationStatus::Approved | ApplicationStatus::Rejected) {
+        return Err((StatusCode::CONFLICT, "Approval or rejection must be recorded through the decision endpoint".to_string()));
+    }
+
+    let application = fetch_application(&db, &application_number).await?;
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+    let result = transition_application(&mut tx, &application, payload.to, &claims).await?;
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit status change: {}", e)))?;
+
+    Ok(Json(BailApplicationResponse {
+        id: result.id,
+        application_number: result.application_number,
+        status: result.status,
+        created_at: result.created_at,
+    }))
+}
+
+
+
+pub async fn get_judge_bail_applications( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Vec<BailApplicationSummary>>, (StatusCode, String)> {
+
+    if claims.role != "judge" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    let judge_id = Uuid::parse_str(&claims.sub)
         .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
 
-    let result = sqlx::query_as::<_, BailApplication>(
+    let applications = sqlx::query_as::<_, BailApplicationSummary>(
         r#"
-        UPDATE bail_applications
-        SET assigned_lawyer_id = $1, status = 'under_review'
-        WHERE application_number = $2
+        SELECT id, application_number, applicant_name, fir_number, status, bail_type, created_at
+        FROM bail_applications
+        WHERE judge_id = $1
+        ORDER BY created_at DESC
+        "#
+    )
+    .bind(judge_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch applications: {}", e)))?;
+
+    Ok(Json(applications))
+}
+
+
+
+pub async fn take_up_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<BailApplicationResponse>, (StatusCode, String)> {
+
+    if claims.role != "judge" {
+        return Err((StatusCode::FORBIDDEN, "Only judges can take up cases".to_string()));
+    }
+
+    let application = fetch_application(&db, &application_number).await?;
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+    let result = transition_application(&mut tx, &application, ApplicationStatus::ListedForHearing, &claims).await?;
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit status change: {}", e)))?;
+
+    Ok(Json(BailApplicationResponse {
+        id: result.id,
+        application_number: result.application_number,
+        status: result.status,
+        created_at: result.created_at,
+    }))
+}
+
+
+
+pub async fn decide_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<DecideBailApplication>, ) -> Result<Json<BailOrder>, (StatusCode, String)> {
+
+    if claims.role != "judge" {
+        return Err((StatusCode::FORBIDDEN, "Only judges can decide cases".to_string()));
+    }
+
+    let judge_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    if application.judge_id != Some(judge_id) {
+        return Err((StatusCode::FORBIDDEN, "Case is not assigned to you".to_string()));
+    }
+
+    validate_order(&payload)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    transition_application(&mut tx, &application, payload.decision.resulting_status(), &claims).await?;
+
+    let order = sqlx::query_as::<_, BailOrder>(
+        r#"
+        INSERT INTO bail_orders (id, application_id, judge_id, decision, order_text, bail_amount, order_date)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
         RETURNING *
         "#
     )
-    .bind(lawyer_id)
-    .bind(&application_number)
+    .bind(Uuid::new_v4())
+    .bind(application.id)
+    .bind(judge_id)
+    .bind(payload.decision)
+    .bind(&payload.order_text)
+    .bind(payload.bail_amount)
+    .bind(payload.order_date)
+    .fetch_one(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record bail order: {}", e)))?;
+
+    for condition in &payload.conditions {
+        sqlx::query(
+            r#"
+            INSERT INTO bail_conditions (id, order_id, application_id, condition_type, description, frequency, start_date, end_date)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(order.id)
+        .bind(application.id)
+        .bind(condition.condition_type)
+        .bind(&condition.description)
+        .bind(condition.frequency)
+        .bind(condition.start_date)
+        .bind(condition.end_date)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record bail condition: {}", e)))?;
+    }
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit decision: {}", e)))?;
+
+    Ok(Json(order))
+}
+
+
+
+pub async fn search_bail_conditions( State(db): State<DbPool>, claims: Claims, Query(query): Query<BailConditionQuery>, ) -> Result<Json<Vec<BailConditionSearchResult>>, (StatusCode, String)> {
+
+    if claims.role != "lawyer" && claims.role != "judge" {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    let results = sqlx::query_as::<_, BailConditionSearchResult>(
+        r#"
+        SELECT a.application_number, a.applicant_name, a.district, a.state, a.police_station, c.*
+        FROM bail_conditions c
+        JOIN bail_applications a ON a.id = c.application_id
+        WHERE ($1::varchar IS NULL OR c.condition_type = $1)
+          AND ($2::varchar IS NULL OR c.frequency = $2)
+          AND ($3::varchar IS NULL OR a.district ILIKE $3)
+          AND ($4::varchar IS NULL OR a.state ILIKE $4)
+          AND ($5::date IS NULL OR (c.start_date <= $5 AND (c.end_date IS NULL OR c.end_date >= $5)))
+        ORDER BY a.district, c.start_date DESC
+        "#
+    )
+    .bind(query.condition_type)
+    .bind(query.frequency)
+    .bind(&query.district)
+    .bind(&query.state)
+    .bind(query.active_on)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to search bail conditions: {}", e)))?;
+
+    Ok(Json(results))
+}
+
+
+
+pub async fn record_check_in( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<CreateCheckIn>, ) -> Result<Json<BailCheckIn>, (StatusCode, String)> {
+
+    let actor_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    let allowed = match claims.role.as_str() {
+        "user" => application.user_id == actor_id,
+        "lawyer" => application.assigned_lawyer_id == Some(actor_id),
+        _ => false,
+    };
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    if application.status != ApplicationStatus::Approved {
+        return Err((StatusCode::CONFLICT, "Check-ins can only be recorded for applications on bail".to_string()));
+    }
+
+    let condition = sqlx::query_as::<_, BailCondition>(
+        "SELECT * FROM bail_conditions WHERE id = $1 AND application_id = $2"
+    )
+    .bind(payload.condition_id)
+    .bind(application.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch bail condition: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Bail condition not found".to_string()))?;
+
+    if condition.condition_type != ConditionType::ReportToPolice {
+        return Err((StatusCode::BAD_REQUEST, "Check-ins can only be recorded against a reporting condition".to_string()));
+    }
+
+    if payload.checked_in_at > Utc::now() {
+        return Err((StatusCode::BAD_REQUEST, "checked_in_at cannot be in the future".to_string()));
+    }
+    if payload.checked_in_at.date_naive() < condition.start_date {
+        return Err((StatusCode::BAD_REQUEST, "checked_in_at cannot be before the condition's start date".to_string()));
+    }
+
+    let check_in = sqlx::query_as::<_, BailCheckIn>(
+        r#"
+        INSERT INTO bail_check_ins (id, condition_id, application_id, checked_in_at, police_station, officer_name, recorded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(condition.id)
+    .bind(application.id)
+    .bind(payload.checked_in_at)
+    .bind(&payload.police_station)
+    .bind(&payload.officer_name)
+    .bind(actor_id)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record check-in: {}", e)))?;
+
+    Ok(Json(check_in))
+}
+
+
+
+pub async fn verify_check_in( State(db): State<DbPool>, Path((application_number, check_in_id)): Path<(String, Uuid)>, claims: Claims, ) -> Result<Json<BailCheckIn>, (StatusCode, String)> {
+
+    if claims.role != "judge" {
+        return Err((StatusCode::FORBIDDEN, "Only judges can verify check-ins".to_string()));
+    }
+
+    let judge_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    if application.judge_id != Some(judge_id) {
+        return Err((StatusCode::FORBIDDEN, "Case is not assigned to you".to_string()));
+    }
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let check_in = sqlx::query_as::<_, BailCheckIn>(
+        r#"
+        UPDATE bail_check_ins
+        SET verified = TRUE, verified_by = $1
+        WHERE id = $2 AND application_id = $3
+        RETURNING *
+        "#
+    )
+    .bind(judge_id)
+    .bind(check_in_id)
+    .bind(application.id)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to verify check-in: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Check-in not found".to_string()))?;
+
+    // Only verified check-ins count, so a period flagged while this one awaited verification is now covered
+    sqlx::query(
+        "DELETE FROM bail_violations WHERE condition_id = $1 AND period_start <= $2 AND period_end > $2"
+    )
+    .bind(check_in.condition_id)
+    .bind(check_in.checked_in_at.date_naive())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to clear missed check-in: {}", e)))?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit transaction: {}", e)))?;
+
+    Ok(Json(check_in))
+}
+
+
+
+pub async fn get_compliance_timeline( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<Vec<ComplianceEvent>>, (StatusCode, String)> {
+
+    let actor_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    let allowed = match claims.role.as_str() {
+        "user" => application.user_id == actor_id,
+        "lawyer" => application.assigned_lawyer_id == Some(actor_id),
+        "judge" => application.judge_id == Some(actor_id),
+        _ => false,
+    };
+    if !allowed {
+        return Err((StatusCode::FORBIDDEN, "Access denied".to_string()));
+    }
+
+    let check_ins = sqlx::query_as::<_, BailCheckIn>(
+        "SELECT * FROM bail_check_ins WHERE application_id = $1"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch check-ins: {}", e)))?;
+
+    let violations = sqlx::query_as::<_, BailViolation>(
+        "SELECT * FROM bail_violations WHERE application_id = $1"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch violations: {}", e)))?;
+
+    let mut timeline: Vec<ComplianceEvent> = check_ins.into_iter().map(ComplianceEvent::CheckIn)
+        .chain(violations.into_iter().map(ComplianceEvent::MissedCheckIn))
+        .collect();
+    timeline.sort_by_key(|event| event.date());
+
+    Ok(Json(timeline))
+}
+
+
+
+pub async fn create_draft( State(db): State<DbPool>, claims: Claims, Json(payload): Json<BailApplicationPatch>, ) -> Result<Json<BailApplicationDraft>, (StatusCode, String)> {
+
+    if claims.role != "user" {
+        return Err((StatusCode::FORBIDDEN, "Only applicants can create drafts".to_string()));
+    }
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let draft = sqlx::query_as::<_, BailApplicationDraft>(
+        r#"
+        INSERT INTO bail_applications (
+            id, user_id, application_number,
+            applicant_name, father_husband_name, age, gender, address, phone_number, email,
+            fir_number, police_station, district, state, date_of_arrest, sections_applied, case_description,
+            bail_type, previous_bail_applications, previous_bail_details,
+            surety_details, medical_condition, family_dependents, employment_details,
+            status
+        ) VALUES (
+            $1, $2, $3, $4, $5, $6, $7, $8, $9, $10,
+            $11, $12, $13, $14, $15, $16, $17,
+            $18, COALESCE($19, FALSE), $20, $21, $22, $23, $24, $25
+        )
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(user_id)
+    .bind(generate_application_number())
+    .bind(&payload.applicant_name)
+    .bind(&payload.father_husband_name)
+    .bind(payload.age)
+    .bind(&payload.gender)
+    .bind(&payload.address)
+    .bind(&payload.phone_number)
+    .bind(&payload.email)
+    .bind(&payload.fir_number)
+    .bind(&payload.police_station)
+    .bind(&payload.district)
+    .bind(&payload.state)
+    .bind(payload.date_of_arrest)
+    .bind(&payload.sections_applied)
+    .bind(&payload.case_description)
+    .bind(&payload.bail_type)
+    .bind(payload.previous_bail_applications)
+    .bind(&payload.previous_bail_details)
+    .bind(&payload.surety_details)
+    .bind(&payload.medical_condition)
+    .bind(&payload.family_dependents)
+    .bind(&payload.employment_details)
+    .bind(ApplicationStatus::Draft)
     .fetch_one(&db)
     .await
-    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to assign lawyer: {}", e)))?;
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to create draft: {}", e)))?;
+
+    Ok(Json(draft))
+}
+
+
+
+pub async fn get_my_drafts( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Vec<BailApplicationDraft>>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let drafts = sqlx::query_as::<_, BailApplicationDraft>(
+        "SELECT * FROM bail_applications WHERE user_id = $1 AND status = 'draft' ORDER BY updated_at DESC"
+    )
+    .bind(user_id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch drafts: {}", e)))?;
+
+    Ok(Json(drafts))
+}
+
+
+
+pub async fn get_draft( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<BailApplicationDraft>, (StatusCode, String)> {
+
+    let draft = fetch_draft(&db, &application_number, &claims).await?;
+
+    Ok(Json(draft))
+}
+
+
+
+pub async fn update_draft( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<BailApplicationPatch>, ) -> Result<Json<BailApplicationDraft>, (StatusCode, String)> {
+
+    let draft = fetch_draft(&db, &application_number, &claims).await?;
+
+    let draft = sqlx::query_as::<_, BailApplicationDraft>(
+        r#"
+        UPDATE bail_applications SET
+            applicant_name = COALESCE($1, applicant_name),
+            father_husband_name = COALESCE($2, father_husband_name),
+            age = COALESCE($3, age),
+            gender = COALESCE($4, gender),
+            address = COALESCE($5, address),
+            phone_number = COALESCE($6, phone_number),
+            email = COALESCE($7, email),
+            fir_number = COALESCE($8, fir_number),
+            police_station = COALESCE($9, police_station),
+            district = COALESCE($10, district),
+            state = COALESCE($11, state),
+            date_of_arrest = COALESCE($12, date_of_arrest),
+            sections_applied = COALESCE($13, sections_applied),
+            case_description = COALESCE($14, case_description),
+            bail_type = COALESCE($15, bail_type),
+            previous_bail_applications = COALESCE($16, previous_bail_applications),
+            previous_bail_details = COALESCE($17, previous_bail_details),
+            surety_details = COALESCE($18, surety_details),
+            medical_condition = COALESCE($19, medical_condition),
+            family_dependents = COALESCE($20, family_dependents),
+            employment_details = COALESCE($21, employment_details)
+        WHERE id = $22 AND status = 'draft'
+        RETURNING *
+        "#
+    )
+    .bind(&payload.applicant_name)
+    .bind(&payload.father_husband_name)
+    .bind(payload.age)
+    .bind(&payload.gender)
+    .bind(&payload.address)
+    .bind(&payload.phone_number)
+    .bind(&payload.email)
+    .bind(&payload.fir_number)
+    .bind(&payload.police_station)
+    .bind(&payload.district)
+    .bind(&payload.state)
+    .bind(payload.date_of_arrest)
+    .bind(&payload.sections_applied)
+    .bind(&payload.case_description)
+    .bind(&payload.bail_type)
+    .bind(payload.previous_bail_applications)
+    .bind(&payload.previous_bail_details)
+    .bind(&payload.surety_details)
+    .bind(&payload.medical_condition)
+    .bind(&payload.family_dependents)
+    .bind(&payload.employment_details)
+    .bind(draft.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update draft: {}", e)))?
+    .ok_or((StatusCode::CONFLICT, "Draft was submitted concurrently".to_string()))?;
+
+    Ok(Json(draft))
+}
+
+
+
+pub async fn submit_draft( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<BailApplicationResponse>, (StatusCode, String)> {
+
+    let draft = fetch_draft(&db, &application_number, &claims).await?;
+    ApplicationStatus::Draft.check_transition(ApplicationStatus::Pending, &claims.role)?;
+
+    let draft_id = draft.id;
+    let payload = draft.into_submission()
+        .map_err(|missing| (StatusCode::UNPROCESSABLE_ENTITY, format!("Missing required fields: {}", missing.join(", "))))?;
+    validate_submission(&payload)?;
+
+    let mut conn = db.acquire().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to acquire connection: {}", e)))?;
+
+    let result = write_application_fields(&mut conn, draft_id, &payload, ApplicationStatus::Draft, ApplicationStatus::Pending).await?
+        .ok_or((StatusCode::CONFLICT, "Draft was already submitted".to_string()))?;
 
     Ok(Json(BailApplicationResponse {
         id: result.id,
@@ -184,3 +912,92 @@ pub async fn assign_lawyer_to_case( State(db): State<DbPool>, Path(application_n
         created_at: result.created_at,
     }))
 }
+
+
+
+pub async fn discard_draft( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let draft = fetch_draft(&db, &application_number, &claims).await?;
+
+    sqlx::query("DELETE FROM bail_applications WHERE id = $1 AND status = 'draft'")
+        .bind(draft.id)
+        .execute(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to discard draft: {}", e)))?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+
+
+pub async fn update_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<BailApplicationPatch>, ) -> Result<Json<BailApplication>, (StatusCode, String)> {
+
+    let user_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    // The owner edits until a lawyer picks the case up; then only that lawyer may
+    let may_edit = match application.status {
+        ApplicationStatus::Pending => claims.role == "user" && application.user_id == user_id,
+        ApplicationStatus::UnderReview => claims.role == "lawyer" && application.assigned_lawyer_id == Some(user_id),
+        _ => false,
+    };
+    if !may_edit {
+        return Err((StatusCode::FORBIDDEN, format!("Application cannot be edited by you while {}", application.status)));
+    }
+
+    let updated = payload.apply_to(CreateBailApplication::from(&application));
+    validate_submission(&updated)?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let result = write_application_fields(&mut tx, application.id, &updated, application.status, application.status).await?
+        .ok_or((StatusCode::CONFLICT, "Application status changed concurrently, please retry".to_string()))?;
+
+    record_revisions(&mut tx, &application, &result, &claims).await?;
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit changes: {}", e)))?;
+
+    Ok(Json(result))
+}
+
+
+
+pub async fn withdraw_bail_application( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<BailApplicationResponse>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+    let result = transition_application(&mut tx, &application, ApplicationStatus::Withdrawn, &claims).await?;
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit status change: {}", e)))?;
+
+    Ok(Json(BailApplicationResponse {
+        id: result.id,
+        application_number: result.application_number,
+        status: result.status,
+        created_at: result.created_at,
+    }))
+}
+
+
+
+pub async fn get_application_revisions( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<Vec<ApplicationRevision>>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let revisions = sqlx::query_as::<_, ApplicationRevision>(
+        "SELECT * FROM application_revisions WHERE application_id = $1 ORDER BY changed_at, field_name"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch revisions: {}", e)))?;
+
+    Ok(Json(revisions))
+}
@@ -1,2 +1,5 @@
 pub mod models;
 pub mod handlers;
+pub mod status;
+pub mod compliance;
+pub mod revisions;
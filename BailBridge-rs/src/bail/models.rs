@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use chrono::{DateTime, Utc};
+use validator::{Validate, ValidationError};
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::{bail::status::ApplicationStatus, db::text_enum, hearings::models::Hearing, sureties::models::Surety};
 
 #[derive(Debug, Serialize, Deserialize, sqlx::FromRow)]
 pub struct BailApplication {
@@ -32,13 +35,14 @@ pub struct BailApplication {
     pub previous_bail_details: Option<String>,
     
     // Supporting Information
+    // Legacy free text kept readable for older rows; see the `sureties` table
     pub surety_details: Option<String>,
     pub medical_condition: Option<String>,
     pub family_dependents: Option<String>,
     pub employment_details: Option<String>,
     
     // Status
-    pub status: String,
+    pub status: ApplicationStatus,
     pub assigned_lawyer_id: Option<Uuid>,
     pub judge_id: Option<Uuid>,
     
@@ -48,27 +52,49 @@ pub struct BailApplication {
     pub submitted_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Deserialize)]
+fn validate_bail_type(bail_type: &str) -> Result<(), ValidationError> {
+    match bail_type {
+        "regular" | "anticipatory" | "interim" => Ok(()),
+        _ => Err(ValidationError::new("bail_type")
+            .with_message("bail_type must be regular, anticipatory or interim".into())),
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateBailApplication {
     // Personal Information
+    #[validate(length(min = 1, max = 255))]
     pub applicant_name: String,
+    #[validate(length(min = 1, max = 255))]
     pub father_husband_name: String,
+    #[validate(range(min = 1, max = 120))]
     pub age: i32,
+    #[validate(length(min = 1, max = 20))]
     pub gender: String,
+    #[validate(length(min = 1))]
     pub address: String,
+    #[validate(length(min = 10, max = 20))]
     pub phone_number: String,
+    #[validate(email)]
     pub email: Option<String>,
     
     // Case Details
+    #[validate(length(min = 1, max = 100))]
     pub fir_number: String,
+    #[validate(length(min = 1, max = 255))]
     pub police_station: String,
+    #[validate(length(min = 1, max = 100))]
     pub district: String,
+    #[validate(length(min = 1, max = 100))]
     pub state: String,
     pub date_of_arrest: DateTime<Utc>,
+    #[validate(length(min = 1))]
     pub sections_applied: String,
+    #[validate(length(min = 1))]
     pub case_description: String,
     
     // Bail Details
+    #[validate(custom(function = "validate_bail_type"))]
     pub bail_type: String,
     pub previous_bail_applications: bool,
     pub previous_bail_details: Option<String>,
@@ -80,11 +106,194 @@ pub struct CreateBailApplication {
     pub employment_details: Option<String>,
 }
 
+/// A saved-but-unsubmitted application. Every case field may still be missing.
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailApplicationDraft {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub application_number: String,
+    
+    // Personal Information
+    pub applicant_name: Option<String>,
+    pub father_husband_name: Option<String>,
+    pub age: Option<i32>,
+    pub gender: Option<String>,
+    pub address: Option<String>,
+    pub phone_number: Option<String>,
+    pub email: Option<String>,
+    
+    // Case Details
+    pub fir_number: Option<String>,
+    pub police_station: Option<String>,
+    pub district: Option<String>,
+    pub state: Option<String>,
+    pub date_of_arrest: Option<DateTime<Utc>>,
+    pub sections_applied: Option<String>,
+    pub case_description: Option<String>,
+    
+    // Bail Details
+    pub bail_type: Option<String>,
+    pub previous_bail_applications: Option<bool>,
+    pub previous_bail_details: Option<String>,
+    
+    // Supporting Information
+    pub surety_details: Option<String>,
+    pub medical_condition: Option<String>,
+    pub family_dependents: Option<String>,
+    pub employment_details: Option<String>,
+    
+    // Status
+    pub status: ApplicationStatus,
+    
+    // Metadata
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Partial application fields for drafts and edits; omitted fields are left unchanged.
+#[derive(Debug, Default, Deserialize)]
+pub struct BailApplicationPatch {
+    // Personal Information
+    pub applicant_name: Option<String>,
+    pub father_husband_name: Option<String>,
+    pub age: Option<i32>,
+    pub gender: Option<String>,
+    pub address: Option<String>,
+    pub phone_number: Option<String>,
+    pub email: Option<String>,
+    
+    // Case Details
+    pub fir_number: Option<String>,
+    pub police_station: Option<String>,
+    pub district: Option<String>,
+    pub state: Option<String>,
+    pub date_of_arrest: Option<DateTime<Utc>>,
+    pub sections_applied: Option<String>,
+    pub case_description: Option<String>,
+    
+    // Bail Details
+    pub bail_type: Option<String>,
+    pub previous_bail_applications: Option<bool>,
+    pub previous_bail_details: Option<String>,
+    
+    // Supporting Information
+    pub surety_details: Option<String>,
+    pub medical_condition: Option<String>,
+    pub family_dependents: Option<String>,
+    pub employment_details: Option<String>,
+}
+
+impl BailApplicationPatch {
+    /// Overlays the provided fields onto an existing submission.
+    pub fn apply_to(self, base: CreateBailApplication) -> CreateBailApplication {
+        CreateBailApplication {
+            applicant_name: self.applicant_name.unwrap_or(base.applicant_name),
+            father_husband_name: self.father_husband_name.unwrap_or(base.father_husband_name),
+            age: self.age.unwrap_or(base.age),
+            gender: self.gender.unwrap_or(base.gender),
+            address: self.address.unwrap_or(base.address),
+            phone_number: self.phone_number.unwrap_or(base.phone_number),
+            email: self.email.or(base.email),
+            fir_number: self.fir_number.unwrap_or(base.fir_number),
+            police_station: self.police_station.unwrap_or(base.police_station),
+            district: self.district.unwrap_or(base.district),
+            state: self.state.unwrap_or(base.state),
+            date_of_arrest: self.date_of_arrest.unwrap_or(base.date_of_arrest),
+            sections_applied: self.sections_applied.unwrap_or(base.sections_applied),
+            case_description: self.case_description.unwrap_or(base.case_description),
+            bail_type: self.bail_type.unwrap_or(base.bail_type),
+            previous_bail_applications: self.previous_bail_applications.unwrap_or(base.previous_bail_applications),
+            previous_bail_details: self.previous_bail_details.or(base.previous_bail_details),
+            surety_details: self.surety_details.or(base.surety_details),
+            medical_condition: self.medical_condition.or(base.medical_condition),
+            family_dependents: self.family_dependents.or(base.family_dependents),
+            employment_details: self.employment_details.or(base.employment_details),
+        }
+    }
+}
+
+impl From<&BailApplication> for CreateBailApplication {
+    fn from(application: &BailApplication) -> Self {
+        CreateBailApplication {
+            applicant_name: application.applicant_name.clone(),
+            father_husband_name: application.father_husband_name.clone(),
+            age: application.age,
+            gender: application.gender.clone(),
+            address: application.address.clone(),
+            phone_number: application.phone_number.clone(),
+            email: application.email.clone(),
+            fir_number: application.fir_number.clone(),
+            police_station: application.police_station.clone(),
+            district: application.district.clone(),
+            state: application.state.clone(),
+            date_of_arrest: application.date_of_arrest,
+            sections_applied: application.sections_applied.clone(),
+            case_description: application.case_description.clone(),
+            bail_type: application.bail_type.clone(),
+            previous_bail_applications: application.previous_bail_applications,
+            previous_bail_details: application.previous_bail_details.clone(),
+            surety_details: application.surety_details.clone(),
+            medical_condition: application.medical_condition.clone(),
+            family_dependents: application.family_dependents.clone(),
+            employment_details: application.employment_details.clone(),
+        }
+    }
+}
+
+impl BailApplicationDraft {
+    /// Turns the draft into a full submission, or lists the required fields still missing.
+    pub fn into_submission(self) -> Result<CreateBailApplication, Vec<&'static str>> {
+        let mut missing = Vec::new();
+
+        macro_rules! required {
+            ($field:ident) => {
+                match self.$field {
+                    Some(value) => value,
+                    None => {
+                        missing.push(stringify!($field));
+                        Default::default()
+                    }
+                }
+            };
+        }
+
+        let submission = CreateBailApplication {
+            applicant_name: required!(applicant_name),
+            father_husband_name: required!(father_husband_name),
+            age: required!(age),
+            gender: required!(gender),
+            address: required!(address),
+            phone_number: required!(phone_number),
+            email: self.email,
+            fir_number: required!(fir_number),
+            police_station: required!(police_station),
+            district: required!(district),
+            state: required!(state),
+            date_of_arrest: required!(date_of_arrest),
+            sections_applied: required!(sections_applied),
+            case_description: required!(case_description),
+            bail_type: required!(bail_type),
+            previous_bail_applications: self.previous_bail_applications.unwrap_or(false),
+            previous_bail_details: self.previous_bail_details,
+            surety_details: self.surety_details,
+            medical_condition: self.medical_condition,
+            family_dependents: self.family_dependents,
+            employment_details: self.employment_details,
+        };
+
+        if missing.is_empty() {
+            Ok(submission)
+        } else {
+            Err(missing)
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 pub struct BailApplicationResponse {
     pub id: Uuid,
     pub application_number: String,
-    pub status: String,
+    pub status: ApplicationStatus,
     pub created_at: DateTime<Utc>,
 }
 
@@ -94,7 +303,185 @@ pub struct BailApplicationSummary {
     pub application_number: String,
     pub applicant_name: String,
     pub fir_number: String,
-    pub status: String,
+    pub status: ApplicationStatus,
     pub bail_type: String,
     pub created_at: DateTime<Utc>,
 }
+
+#[derive(Debug, Deserialize)]
+pub struct TransitionRequest {
+    pub to: ApplicationStatus,
+}
+
+text_enum! {
+    pub enum Decision {
+        Granted => "granted",
+        Rejected => "rejected",
+        PartlyGranted => "partly_granted",
+    }
+}
+
+impl Decision {
+    pub fn resulting_status(&self) -> ApplicationStatus {
+        match self {
+            Decision::Granted | Decision::PartlyGranted => ApplicationStatus::Approved,
+            Decision::Rejected => ApplicationStatus::Rejected,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailOrder {
+    pub id: Uuid,
+    pub application_id: Uuid,
+    pub judge_id: Uuid,
+    pub decision: Decision,
+    pub order_text: String,
+    pub bail_amount: Option<i64>,
+    pub order_date: NaiveDate,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecideBailApplication {
+    pub decision: Decision,
+    pub order_text: String,
+    pub bail_amount: Option<i64>,
+    pub order_date: NaiveDate,
+    #[serde(default)]
+    pub conditions: Vec<CreateBailCondition>,
+}
+
+text_enum! {
+    pub enum ConditionType {
+        SurrenderPassport => "surrender_passport",
+        ReportToPolice => "report_to_police",
+        RestrictTravel => "restrict_travel",
+        NoWitnessContact => "no_witness_contact",
+        Other => "other",
+    }
+}
+
+text_enum! {
+    pub enum ConditionFrequency {
+        Once => "once",
+        Daily => "daily",
+        Weekly => "weekly",
+        Fortnightly => "fortnightly",
+        Monthly => "monthly",
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailCondition {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub application_id: Uuid,
+    pub condition_type: ConditionType,
+    pub description: String,
+    pub frequency: Option<ConditionFrequency>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBailCondition {
+    pub condition_type: ConditionType,
+    pub description: String,
+    pub frequency: Option<ConditionFrequency>,
+    pub start_date: NaiveDate,
+    pub end_date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BailConditionQuery {
+    pub condition_type: Option<ConditionType>,
+    pub frequency: Option<ConditionFrequency>,
+    pub district: Option<String>,
+    pub state: Option<String>,
+    /// Only conditions in force on this date
+    pub active_on: Option<NaiveDate>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailConditionSearchResult {
+    pub application_number: String,
+    pub applicant_name: String,
+    pub district: String,
+    pub state: String,
+    pub police_station: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub condition: BailCondition,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BailApplicationDetail {
+    #[serde(flatten)]
+    pub application: BailApplication,
+    pub order: Option<BailOrder>,
+    pub conditions: Vec<BailCondition>,
+    pub upcoming_hearings: Vec<Hearing>,
+    pub sureties: Vec<Surety>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailCheckIn {
+    pub id: Uuid,
+    pub condition_id: Uuid,
+    pub application_id: Uuid,
+    pub checked_in_at: DateTime<Utc>,
+    pub police_station: String,
+    pub officer_name: String,
+    pub verified: bool,
+    pub verified_by: Option<Uuid>,
+    pub recorded_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateCheckIn {
+    pub condition_id: Uuid,
+    pub checked_in_at: DateTime<Utc>,
+    pub police_station: String,
+    pub officer_name: String,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct BailViolation {
+    pub id: Uuid,
+    pub condition_id: Uuid,
+    pub application_id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub detected_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ComplianceEvent {
+    CheckIn(BailCheckIn),
+    MissedCheckIn(BailViolation),
+}
+
+impl ComplianceEvent {
+    pub fn date(&self) -> NaiveDate {
+        match self {
+            ComplianceEvent::CheckIn(check_in) => check_in.checked_in_at.date_naive(),
+            ComplianceEvent::MissedCheckIn(violation) => violation.period_start,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApplicationRevision {
+    pub id: Uuid,
+    pub application_id: Uuid,
+    pub field_name: String,
+    pub old_value: Option<serde_json::Value>,
+    pub new_value: Option<serde_json::Value>,
+    pub changed_by: Uuid,
+    pub changed_by_role: String,
+    pub changed_at: DateTime<Utc>,
+}
@@ -0,0 +1,57 @@
+use axum::http::StatusCode;
+use serde_json::Value;
+use uuid::Uuid;
+
+use crate::{auth::jwt::Claims, bail::models::BailApplication};
+
+// Bookkeeping columns that change on every write and carry no history value
+const UNTRACKED_FIELDS: &[&str] = &["id", "user_id", "application_number", "created_at", "updated_at"];
+
+fn to_fields(application: &BailApplication) -> Result<serde_json::Map<String, Value>, (StatusCode, String)> {
+    match serde_json::to_value(application) {
+        Ok(Value::Object(fields)) => Ok(fields),
+        Ok(_) => Err((StatusCode::INTERNAL_SERVER_ERROR, "Application did not serialize to an object".to_string())),
+        Err(e) => Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to serialize application: {}", e))),
+    }
+}
+
+/// Writes one `application_revisions` row for every field that differs
+/// between `before` and `after`.
+pub async fn record_revisions( conn: &mut sqlx::PgConnection, before: &BailApplication, after: &BailApplication, claims: &Claims, ) -> Result<(), (StatusCode, String)> {
+
+    let actor_id = Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))?;
+
+    let old_fields = to_fields(before)?;
+    let new_fields = to_fields(after)?;
+
+    for (field, new_value) in &new_fields {
+        if UNTRACKED_FIELDS.contains(&field.as_str()) {
+            continue;
+        }
+
+        let old_value = old_fields.get(field).unwrap_or(&Value::Null);
+        if old_value == new_value {
+            continue;
+        }
+
+        sqlx::query(
+            r#"
+            INSERT INTO application_revisions (id, application_id, field_name, old_value, new_value, changed_by, changed_by_role)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#
+        )
+        .bind(Uuid::new_v4())
+        .bind(after.id)
+        .bind(field)
+        .bind(old_value)
+        .bind(new_value)
+        .bind(actor_id)
+        .bind(&claims.role)
+        .execute(&mut *conn)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record revision: {}", e)))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use axum::http::StatusCode;
+
+use crate::db::text_enum;
+
+text_enum! {
+    pub enum ApplicationStatus {
+        Draft => "draft",
+        Pending => "pending",
+        UnderReview => "under_review",
+        ListedForHearing => "listed_for_hearing",
+        Approved => "approved",
+        Rejected => "rejected",
+        Withdrawn => "withdrawn",
+    }
+}
+
+struct Transition {
+    from: ApplicationStatus,
+    to: ApplicationStatus,
+    roles: &'static [&'static str],
+}
+
+// The single source of truth for how a case moves through its lifecycle.
+// Any (from, to) pair not listed here is an illegal move.
+const TRANSITIONS: &[Transition] = &[
+    Transition { from: ApplicationStatus::Draft, to: ApplicationStatus::Pending, roles: &["user"] },
+    Transition { from: ApplicationStatus::Pending, to: ApplicationStatus::UnderReview, roles: &["lawyer"] },
+    Transition { from: ApplicationStatus::Pending, to: ApplicationStatus::Withdrawn, roles: &["user"] },
+    Transition { from: ApplicationStatus::UnderReview, to: ApplicationStatus::ListedForHearing, roles: &["judge"] },
+    Transition { from: ApplicationStatus::UnderReview, to: ApplicationStatus::Withdrawn, roles: &["user", "lawyer"] },
+    Transition { from: ApplicationStatus::ListedForHearing, to: ApplicationStatus::Approved, roles: &["judge"] },
+    Transition { from: ApplicationStatus::ListedForHearing, to: ApplicationStatus::Rejected, roles: &["judge"] },
+    Transition { from: ApplicationStatus::ListedForHearing, to: ApplicationStatus::Withdrawn, roles: &["user", "lawyer"] },
+];
+
+#[derive(Debug)]
+pub enum TransitionError {
+    IllegalMove { from: ApplicationStatus, to: ApplicationStatus },
+    RoleNotPermitted { role: String, from: ApplicationStatus, to: ApplicationStatus },
+}
+
+impl ApplicationStatus {
+    /// Checks the transition table for a move from `self` to `to` by `role`.
+    pub fn check_transition(self, to: ApplicationStatus, role: &str) -> Result<(), TransitionError> {
+        let transition = TRANSITIONS
+            .iter()
+            .find(|t| t.from == self && t.to == to)
+            .ok_or(TransitionError::IllegalMove { from: self, to })?;
+
+        if !transition.roles.contains(&role) {
+            return Err(TransitionError::RoleNotPermitted { role: role.to_string(), from: self, to });
+        }
+
+        Ok(())
+    }
+}
+
+impl From<TransitionError> for (StatusCode, String) {
+    fn from(err: TransitionError) -> Self {
+        match err {
+            TransitionError::IllegalMove { from, to } => (
+                StatusCode::CONFLICT,
+                format!("Cannot move application from '{}' to '{}'", from, to),
+            ),
+            TransitionError::RoleNotPermitted { role, from, to } => (
+                StatusCode::FORBIDDEN,
+                format!("Role '{}' cannot move application from '{}' to '{}'", role, from, to),
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ApplicationStatus::*;
+
+    const ALL: [ApplicationStatus; 7] = [Draft, Pending, UnderReview, ListedForHearing, Approved, Rejected, Withdrawn];
+
+    #[test]
+    fn follows_the_case_lifecycle() {
+        assert!(Draft.check_transition(Pending, "user").is_ok());
+        assert!(Pending.check_transition(UnderReview, "lawyer").is_ok());
+        assert!(UnderReview.check_transition(ListedForHearing, "judge").is_ok());
+        assert!(ListedForHearing.check_transition(Approved, "judge").is_ok());
+        assert!(ListedForHearing.check_transition(Rejected, "judge").is_ok());
+        assert!(ListedForHearing.check_transition(Withdrawn, "lawyer").is_ok());
+    }
+
+    #[test]
+    fn only_listed_roles_can_move_a_case() {
+        let err = Pending.check_transition(UnderReview, "judge").unwrap_err();
+        assert!(matches!(err, TransitionError::RoleNotPermitted { .. }));
+        assert_eq!(<(StatusCode, String)>::from(err).0, StatusCode::FORBIDDEN);
+
+        assert!(Pending.check_transition(Withdrawn, "lawyer").is_err());
+        assert!(ListedForHearing.check_transition(Approved, "admin").is_err());
+    }
+
+    #[test]
+    fn decided_and_withdrawn_cases_are_final() {
+        for from in [Approved, Rejected, Withdrawn] {
+            for to in ALL {
+                for role in ["user", "lawyer", "judge", "admin"] {
+                    let err = from.check_transition(to, role).unwrap_err();
+                    assert!(matches!(err, TransitionError::IllegalMove { .. }), "{} -> {} by {}", from, to, role);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn illegal_moves_are_conflicts() {
+        let err = Draft.check_transition(Approved, "judge").unwrap_err();
+        assert_eq!(
+            <(StatusCode, String)>::from(err),
+            (StatusCode::CONFLICT, "Cannot move application from 'draft' to 'approved'".to_string()),
+        );
+
+        for status in ALL {
+            assert!(status.check_transition(status, "judge").is_err());
+        }
+    }
+}
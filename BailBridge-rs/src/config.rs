@@ -1,18 +1,362 @@
-use std::env;
+use std::{collections::HashMap, env, net::IpAddr, sync::Arc};
+
+use ipnet::IpNet;
+use jsonwebtoken::Algorithm;
+
+use crate::{
+    auth::{keys::JwtKeys, password_policy::{BreachedPasswords, PasswordPolicy}},
+    models::UserRole,
+};
+
+#[derive(Clone)]
+pub enum DocumentStoreConfig {
+    Local {
+        root: String,
+    },
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        access_key: String,
+        secret_key: String,
+    },
+}
+
+#[derive(Clone)]
+pub enum MailerConfig {
+    File {
+        outbox: String,
+        from: String,
+    },
+    Smtp {
+        host: String,
+        port: u16,
+        username: Option<String>,
+        password: Option<String>,
+        from: String,
+    },
+}
+
+/// Token bucket size: `requests` at once, refilled evenly over `per_secs`.
+#[derive(Clone, Copy, Debug)]
+pub struct RateBudget {
+    pub requests: u32,
+    pub per_secs: u32,
+}
+
+#[derive(Clone)]
+pub enum RateLimitBackend {
+    Memory,
+    Postgres,
+}
+
+#[derive(Clone)]
+pub struct RateLimitConfig {
+    pub backend: RateLimitBackend,
+    pub groups: HashMap<String, RateBudget>, // route group -> budget per caller
+    pub roles: HashMap<String, RateBudget>, // role -> budget per user across all routes
+}
+
+// Budgets are written as `<requests>/<seconds>`, e.g. `20/60`
+fn rate_budget_from_env(var: &str, default: RateBudget) -> RateBudget {
+    env::var(var)
+        .ok()
+        .and_then(|v| {
+            let (requests, per_secs) = v.split_once('/')?;
+            Some(RateBudget {
+                requests: requests.trim().parse().ok()?,
+                per_secs: per_secs.trim().parse().ok()?,
+            })
+        })
+        .filter(|budget| budget.requests > 0 && budget.per_secs > 0)
+        .unwrap_or(default)
+}
+
+/// One external OpenID Connect identity provider users can log in with.
+#[derive(Clone)]
+pub struct OidcProviderConfig {
+    pub name: String,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: Option<String>, // None for public clients, which rely on PKCE alone
+    pub redirect_uri: String,
+    pub scopes: String,
+    pub role_claim: String, // dotted path into the ID token, e.g. `groups` or `realm_access.roles`
+    pub role_map: Vec<(String, UserRole)>, // claim value -> role, in priority order
+    pub default_role: Option<UserRole>, // for identities no entry in `role_map` matches
+}
+
+// Providers are listed in OIDC_PROVIDERS and configured through OIDC_<NAME>_* variables
+fn oidc_provider_from_env(name: &str, app_base_url: &str) -> OidcProviderConfig {
+    let var = |key: &str| format!("OIDC_{}_{}", name.to_uppercase().replace('-', "_"), key);
+    let required = |key: &str| env::var(var(key)).unwrap_or_else(|_| panic!("{} must be set", var(key)));
+    let parse_role = |role: &str| {
+        role.trim()
+            .parse::<UserRole>()
+            .unwrap_or_else(|e| panic!("Invalid role in {}: {}", var("ROLE_MAP"), e))
+    };
+
+    OidcProviderConfig {
+        name: name.to_string(),
+        issuer: required("ISSUER").trim_end_matches('/').to_string(),
+        client_id: required("CLIENT_ID"),
+        client_secret: env::var(var("CLIENT_SECRET")).ok().filter(|s| !s.is_empty()),
+        redirect_uri: env::var(var("REDIRECT_URI"))
+            .unwrap_or_else(|_| format!("{}/auth/oidc/{}/callback", app_base_url, name)),
+        scopes: env::var(var("SCOPES")).unwrap_or_else(|_| "openid email profile".to_string()),
+        role_claim: env::var(var("ROLE_CLAIM")).unwrap_or_else(|_| "groups".to_string()),
+        role_map: env::var(var("ROLE_MAP"))
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|entry| entry.split_once('='))
+            .map(|(value, role)| (value.trim().to_string(), parse_role(role)))
+            .collect(),
+        default_role: env::var(var("DEFAULT_ROLE")).ok().filter(|r| !r.is_empty()).map(|r| parse_role(&r)),
+    }
+}
 
 #[derive(Clone)]
 pub struct Config {
     pub database_url: String,
-    pub jwt_secret: String,
+    pub jwt_keys: Arc<JwtKeys>,
+    pub access_token_ttl_secs: i64,
+    pub refresh_token_ttl_secs: i64,
+    pub revocation_sync_interval_secs: u64,
+    pub compliance_scan_interval_secs: u64,
+    pub document_store: DocumentStoreConfig,
+    pub max_document_size_bytes: usize,
+    pub max_active_bails_per_surety: i64,
+    pub mailer: MailerConfig,
+    pub app_base_url: String,
+    pub password_reset_ttl_secs: i64,
+    pub email_verification_ttl_secs: i64,
+    pub mfa_required_roles: Vec<String>,
+    pub mfa_challenge_ttl_secs: i64,
+    pub totp_issuer: String,
+    pub login_max_failures_per_account: i32,
+    pub login_max_failures_per_ip: i32,
+    pub login_failure_window_secs: i64,
+    pub login_lockout_base_secs: i64,
+    pub login_lockout_max_secs: i64,
+    pub rate_limit: RateLimitConfig,
+    pub oidc_providers: Vec<OidcProviderConfig>,
+    pub max_api_keys_per_user: i64,
+    pub password_policy: Arc<PasswordPolicy>,
+    pub trusted_proxies: Vec<IpNet>, // peers whose X-Forwarded-For is believed
 }
 
 impl Config {
     pub fn from_env() -> Self {
         let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
-        let jwt_secret = env::var("JWT_SECRET").expect("JWT_SECRET must be set");
+        let jwt_secret = env::var("JWT_SECRET").ok();
+        let jwt_keys = Arc::new(match env::var("JWT_ALGORITHM").as_deref() {
+            Ok(name @ ("RS256" | "EdDSA")) => {
+                let algorithm = if name == "RS256" { Algorithm::RS256 } else { Algorithm::EdDSA };
+                let signing_key_file = env::var("JWT_SIGNING_KEY_FILE").expect("JWT_SIGNING_KEY_FILE must be set");
+                let verification_key_files: Vec<String> = env::var("JWT_VERIFICATION_KEY_FILES")
+                    .unwrap_or_default()
+                    .split(',')
+                    .map(|file| file.trim().to_string())
+                    .filter(|file| !file.is_empty())
+                    .collect();
+                JwtKeys::from_files(algorithm, &signing_key_file, &verification_key_files, jwt_secret.as_deref())
+                    .unwrap_or_else(|e| panic!("Failed to load JWT keys: {}", e))
+            }
+            Ok("HS256") | Err(_) => JwtKeys::hmac(&jwt_secret.expect("JWT_SECRET must be set")),
+            Ok(other) => panic!("Unsupported JWT_ALGORITHM: {}", other),
+        });
+        let access_token_ttl_secs = env::var("ACCESS_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15 * 60);
+        let refresh_token_ttl_secs = env::var("REFRESH_TOKEN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30 * 24 * 60 * 60);
+        let revocation_sync_interval_secs = env::var("REVOCATION_SYNC_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let compliance_scan_interval_secs = env::var("COMPLIANCE_SCAN_INTERVAL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3600);
+        let document_store = match env::var("DOCUMENT_STORE").as_deref() {
+            Ok("s3") => DocumentStoreConfig::S3 {
+                endpoint: env::var("S3_ENDPOINT").expect("S3_ENDPOINT must be set"),
+                bucket: env::var("S3_BUCKET").expect("S3_BUCKET must be set"),
+                region: env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+                access_key: env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY must be set"),
+                secret_key: env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY must be set"),
+            },
+            _ => DocumentStoreConfig::Local {
+                root: env::var("DOCUMENT_STORE_PATH").unwrap_or_else(|_| "./uploads".to_string()),
+            },
+        };
+        let max_document_size_bytes = env::var("MAX_DOCUMENT_SIZE_BYTES")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10 * 1024 * 1024);
+        let max_active_bails_per_surety = env::var("MAX_ACTIVE_BAILS_PER_SURETY")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(2);
+        let max_api_keys_per_user = env::var("MAX_API_KEYS_PER_USER")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let mail_from = env::var("MAIL_FROM").unwrap_or_else(|_| "BailBridge <no-reply@bailbridge.local>".to_string());
+        let mailer = match env::var("MAILER").as_deref() {
+            Ok("smtp") => MailerConfig::Smtp {
+                host: env::var("SMTP_HOST").expect("SMTP_HOST must be set"),
+                port: env::var("SMTP_PORT")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(587),
+                username: env::var("SMTP_USERNAME").ok(),
+                password: env::var("SMTP_PASSWORD").ok(),
+                from: mail_from,
+            },
+            _ => MailerConfig::File {
+                outbox: env::var("MAIL_OUTBOX_PATH").unwrap_or_else(|_| "./outbox".to_string()),
+                from: mail_from,
+            },
+        };
+        let app_base_url = env::var("APP_BASE_URL")
+            .unwrap_or_else(|_| "http://localhost:3000".to_string())
+            .trim_end_matches('/')
+            .to_string();
+        let password_reset_ttl_secs = env::var("PASSWORD_RESET_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60 * 60);
+        let email_verification_ttl_secs = env::var("EMAIL_VERIFICATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(48 * 60 * 60);
+        let mfa_required_roles = env::var("MFA_REQUIRED_ROLES")
+            .unwrap_or_else(|_| "lawyer,judge,admin".to_string())
+            .split(',')
+            .map(|role| role.trim().to_lowercase())
+            .filter(|role| !role.is_empty())
+            .collect();
+        let mfa_challenge_ttl_secs = env::var("MFA_CHALLENGE_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5 * 60);
+        let totp_issuer = env::var("TOTP_ISSUER").unwrap_or_else(|_| "BailBridge".to_string());
+        let login_max_failures_per_account = env::var("LOGIN_MAX_FAILURES_PER_ACCOUNT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(5);
+        let login_max_failures_per_ip = env::var("LOGIN_MAX_FAILURES_PER_IP")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(20);
+        let login_failure_window_secs = env::var("LOGIN_FAILURE_WINDOW_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(15 * 60);
+        let login_lockout_base_secs = env::var("LOGIN_LOCKOUT_BASE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(30);
+        let login_lockout_max_secs = env::var("LOGIN_LOCKOUT_MAX_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(60 * 60);
+        let rate_limit = RateLimitConfig {
+            backend: match env::var("RATE_LIMIT_BACKEND").as_deref() {
+                Ok("postgres") => RateLimitBackend::Postgres,
+                _ => RateLimitBackend::Memory,
+            },
+            groups: [
+                ("auth", 20, 60),
+                ("api", 600, 60),
+                ("uploads", 30, 3600),
+                ("admin", 300, 60),
+            ]
+            .into_iter()
+            .map(|(group, requests, per_secs)| {
+                let var = format!("RATE_LIMIT_{}", group.to_uppercase());
+                (group.to_string(), rate_budget_from_env(&var, RateBudget { requests, per_secs }))
+            })
+            .collect(),
+            roles: [
+                ("user", 120, 60),
+                ("lawyer", 300, 60),
+                ("judge", 300, 60),
+                ("clerk", 300, 60),
+                ("admin", 600, 60),
+            ]
+            .into_iter()
+            .map(|(role, requests, per_secs)| {
+                let var = format!("RATE_LIMIT_ROLE_{}", role.to_uppercase());
+                (role.to_string(), rate_budget_from_env(&var, RateBudget { requests, per_secs }))
+            })
+            .collect(),
+        };
+        let oidc_providers = env::var("OIDC_PROVIDERS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|name| name.trim().to_lowercase())
+            .filter(|name| !name.is_empty())
+            .map(|name| oidc_provider_from_env(&name, &app_base_url))
+            .collect();
+        let password_min_length = env::var("PASSWORD_MIN_LENGTH")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(10);
+        let password_min_score = env::var("PASSWORD_MIN_SCORE")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(3);
+        let breached_passwords = env::var("PASSWORD_BREACH_LIST_PATH").ok().filter(|p| !p.is_empty()).map(|path| {
+            let min_count = env::var("PASSWORD_BREACH_MIN_COUNT")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(1);
+            BreachedPasswords::open(&path, min_count).unwrap_or_else(|e| panic!("{}", e))
+        });
+        let trusted_proxies = env::var("TRUSTED_PROXIES")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| {
+                entry.parse::<IpNet>()
+                    .or_else(|_| entry.parse::<IpAddr>().map(IpNet::from))
+                    .unwrap_or_else(|_| panic!("Invalid address in TRUSTED_PROXIES: {}", entry))
+            })
+            .collect();
+        let password_policy = Arc::new(PasswordPolicy::new(password_min_length, password_min_score, breached_passwords));
         Config {
             database_url,
-            jwt_secret,
+            jwt_keys,
+            access_token_ttl_secs,
+            refresh_token_ttl_secs,
+            revocation_sync_interval_secs,
+            compliance_scan_interval_secs,
+            document_store,
+            max_document_size_bytes,
+            max_active_bails_per_surety,
+            mailer,
+            app_base_url,
+            password_reset_ttl_secs,
+            email_verification_ttl_secs,
+            mfa_required_roles,
+            mfa_challenge_ttl_secs,
+            totp_issuer,
+            login_max_failures_per_account,
+            login_max_failures_per_ip,
+            login_failure_window_secs,
+            login_lockout_base_secs,
+            login_lockout_max_secs,
+            rate_limit,
+            oidc_providers,
+            max_api_keys_per_user,
+            password_policy,
+            trusted_proxies,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use axum::{
+    Json, Extension,
+    extract::{State, Path, Multipart},
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    documents::{models::{Document, DocumentType}, store::DocumentStore},
+    bail::handlers::{fetch_application, ensure_can_view},
+    auth::jwt::Claims,
+    config::Config,
+};
+
+const ALLOWED_MIME_TYPES: &[&str] = &["application/pdf", "image/jpeg", "image/png"];
+
+// Trust the file contents, not the client-supplied Content-Type
+fn sniff_mime_type(bytes: &[u8]) -> Option<&'static str> {
+    if bytes.starts_with(b"%PDF-") {
+        Some("application/pdf")
+    } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else {
+        None
+    }
+}
+
+
+
+pub async fn upload_document( State(db): State<DbPool>, Extension(store): Extension<Arc<dyn DocumentStore>>, Extension(config): Extension<Config>, Path(application_number): Path<String>, claims: Claims, mut multipart: Multipart, ) -> Result<Json<Document>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    let user_id = ensure_can_view(&application, &claims)?;
+
+    let may_upload = application.user_id == user_id || application.assigned_lawyer_id == Some(user_id);
+    if !may_upload {
+        return Err((StatusCode::FORBIDDEN, "Only the applicant or assigned lawyer can upload documents".to_string()));
+    }
+
+    let mut document_type: Option<DocumentType> = None;
+    let mut file: Option<(String, Vec<u8>, String)> = None;
+
+    while let Some(mut field) = multipart.next_field().await
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid multipart body: {}", e)))?
+    {
+        match field.name() {
+            Some("document_type") => {
+                let value = field.text().await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid document_type: {}", e)))?;
+                document_type = Some(value.parse().map_err(|e| (StatusCode::BAD_REQUEST, e))?);
+            }
+            Some("file") => {
+                let file_name = field.file_name().unwrap_or("document").to_string();
+                let mut bytes = Vec::new();
+                let mut hasher = Sha256::new();
+
+                while let Some(chunk) = field.chunk().await
+                    .map_err(|e| (StatusCode::BAD_REQUEST, format!("Failed to read upload: {}", e)))?
+                {
+                    if bytes.len() + chunk.len() > config.max_document_size_bytes {
+                        return Err((
+                            StatusCode::PAYLOAD_TOO_LARGE,
+                            format!("Document exceeds the {} byte limit", config.max_document_size_bytes),
+                        ));
+                    }
+                    hasher.update(&chunk);
+                    bytes.extend_from_slice(&chunk);
+                }
+
+                file = Some((file_name, bytes, hex::encode(hasher.finalize())));
+            }
+            _ => {}
+        }
+    }
+
+    let document_type = document_type
+        .ok_or((StatusCode::BAD_REQUEST, "Missing document_type field".to_string()))?;
+    let (file_name, bytes, sha256) = file
+        .ok_or((StatusCode::BAD_REQUEST, "Missing file field".to_string()))?;
+
+    let mime_type = sniff_mime_type(&bytes)
+        .filter(|mime| ALLOWED_MIME_TYPES.contains(mime))
+        .ok_or((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Only {} documents are accepted", ALLOWED_MIME_TYPES.join(", ")),
+        ))?;
+
+    let document_id = Uuid::new_v4();
+    let storage_key = format!("{}/{}", application.id, document_id);
+    let size_bytes = bytes.len() as i64;
+
+    store.put(&storage_key, mime_type, bytes).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let result = sqlx::query_as::<_, Document>(
+        r#"
+        INSERT INTO documents (id, application_id, document_type, file_name, storage_key, sha256, size_bytes, mime_type, uploaded_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+        RETURNING *
+        "#
+    )
+    .bind(document_id)
+    .bind(application.id)
+    .bind(document_type)
+    .bind(&file_name)
+    .bind(&storage_key)
+    .bind(&sha256)
+    .bind(size_bytes)
+    .bind(mime_type)
+    .bind(user_id)
+    .fetch_one(&db)
+    .await;
+
+    match result {
+        Ok(document) => Ok(Json(document)),
+        Err(e) => {
+            // Don't leave an orphaned blob behind
+            let _ = store.delete(&storage_key).await;
+            Err((StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to save document: {}", e)))
+        }
+    }
+}
+
+
+
+pub async fn list_documents( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<Vec<Document>>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let documents = sqlx::query_as::<_, Document>(
+        "SELECT * FROM documents WHERE application_id = $1 ORDER BY created_at"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch documents: {}", e)))?;
+
+    Ok(Json(documents))
+}
+
+
+
+pub async fn download_document( State(db): State<DbPool>, Extension(store): Extension<Arc<dyn DocumentStore>>, Path((application_number, document_id)): Path<(String, Uuid)>, claims: Claims, ) -> Result<impl IntoResponse, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let document = sqlx::query_as::<_, Document>(
+        "SELECT * FROM documents WHERE id = $1 AND application_id = $2"
+    )
+    .bind(document_id)
+    .bind(application.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch document: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Document not found".to_string()))?;
+
+    let bytes = store.get(&document.storage_key).await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e))?;
+
+    let disposition = format!("attachment; filename=\"{}\"", document.file_name.replace('"', ""));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, document.mime_type),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniffs_allowed_types_from_their_magic_bytes() {
+        assert_eq!(sniff_mime_type(b"%PDF-1.7\n..."), Some("application/pdf"));
+        assert_eq!(sniff_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0, 0x00]), Some("image/jpeg"));
+        assert_eq!(sniff_mime_type(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00]), Some("image/png"));
+    }
+
+    #[test]
+    fn rejects_other_or_truncated_content() {
+        assert_eq!(sniff_mime_type(b""), None);
+        assert_eq!(sniff_mime_type(b"%PDF"), None);
+        assert_eq!(sniff_mime_type(b"<html><script>"), None);
+        assert_eq!(sniff_mime_type(b"GIF89a"), None);
+        assert_eq!(sniff_mime_type(&[0x89, b'P', b'N', b'G']), None);
+    }
+}
@@ -0,0 +1,31 @@
+use serde::Serialize;
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+use crate::db::text_enum;
+
+text_enum! {
+    pub enum DocumentType {
+        FirCopy => "fir_copy",
+        ArrestMemo => "arrest_memo",
+        MedicalCertificate => "medical_certificate",
+        SuretyIdProof => "surety_id_proof",
+        PreviousBailOrder => "previous_bail_order",
+        Other => "other",
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Document {
+    pub id: Uuid,
+    pub application_id: Uuid,
+    pub document_type: DocumentType,
+    pub file_name: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub sha256: String,
+    pub size_bytes: i64,
+    pub mime_type: String,
+    pub uploaded_by: Uuid,
+    pub created_at: DateTime<Utc>,
+}
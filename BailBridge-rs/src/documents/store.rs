@@ -0,0 +1,310 @@
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{Client, Method, Url};
+use sha2::{Digest, Sha256};
+
+use crate::config::DocumentStoreConfig;
+
+/// Blob storage for uploaded documents, addressed by an opaque key.
+#[async_trait]
+pub trait DocumentStore: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), String>;
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String>;
+    async fn delete(&self, key: &str) -> Result<(), String>;
+}
+
+pub fn create_document_store(config: &DocumentStoreConfig) -> Arc<dyn DocumentStore> {
+    match config {
+        DocumentStoreConfig::Local { root } => Arc::new(LocalDocumentStore::new(root)),
+        DocumentStoreConfig::S3 { endpoint, bucket, region, access_key, secret_key } => Arc::new(
+            S3DocumentStore::new(endpoint, bucket, region, access_key, secret_key),
+        ),
+    }
+}
+
+pub struct LocalDocumentStore {
+    root: PathBuf,
+}
+
+impl LocalDocumentStore {
+    pub fn new(root: &str) -> Self {
+        LocalDocumentStore { root: PathBuf::from(root) }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf, String> {
+        // Keys are generated by us, but never let one escape the root
+        if key.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return Err(format!("Invalid document key: {}", key));
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl DocumentStore for LocalDocumentStore {
+    async fn put(&self, key: &str, _content_type: &str, bytes: Vec<u8>) -> Result<(), String> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create directory: {}", e))?;
+        }
+        tokio::fs::write(&path, bytes)
+            .await
+            .map_err(|e| format!("Failed to write document: {}", e))
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        tokio::fs::read(self.path_for(key)?)
+            .await
+            .map_err(|e| format!("Failed to read document: {}", e))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        tokio::fs::remove_file(self.path_for(key)?)
+            .await
+            .map_err(|e| format!("Failed to delete document: {}", e))
+    }
+}
+
+/// S3-compatible backend using path-style URLs and SigV4 signing, so it
+/// works against AWS S3 as well as MinIO and similar local stand-ins.
+pub struct S3DocumentStore {
+    client: Client,
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = HmacSha256::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+impl S3DocumentStore {
+    pub fn new(endpoint: &str, bucket: &str, region: &str, access_key: &str, secret_key: &str) -> Self {
+        S3DocumentStore {
+            client: Client::new(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            bucket: bucket.to_string(),
+            region: region.to_string(),
+            access_key: access_key.to_string(),
+            secret_key: secret_key.to_string(),
+        }
+    }
+
+    async fn send(&self, method: Method, key: &str, content_type: Option<&str>, body: Vec<u8>) -> Result<reqwest::Response, String> {
+        let url = Url::parse(&format!("{}/{}/{}", self.endpoint, self.bucket, key))
+            .map_err(|e| format!("Invalid S3 URL: {}", e))?;
+        let host = match url.port() {
+            Some(port) => format!("{}:{}", url.host_str().unwrap_or_default(), port),
+            None => url.host_str().unwrap_or_default().to_string(),
+        };
+
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date_stamp = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, url.path(), host, payload_hash, amz_date, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date_stamp, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let signing_key = [self.region.as_str(), "s3", "aws4_request"]
+            .iter()
+            .fold(hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), &date_stamp), |key, part| {
+                hmac_sha256(&key, part)
+            });
+        let signature = hex::encode(hmac_sha256(&signing_key, &string_to_sign));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            self.access_key, scope, signature
+        );
+
+        let mut request = self.client
+            .request(method, url)
+            .header("x-amz-date", amz_date)
+            .header("x-amz-content-sha256", payload_hash)
+            .header("Authorization", authorization);
+        if let Some(content_type) = content_type {
+            request = request.header("Content-Type", content_type);
+        }
+
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| format!("S3 request failed: {}", e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("S3 returned {} for {}", response.status(), key));
+        }
+        Ok(response)
+    }
+}
+
+#[async_trait]
+impl DocumentStore for S3DocumentStore {
+    async fn put(&self, key: &str, content_type: &str, bytes: Vec<u8>) -> Result<(), String> {
+        self.send(Method::PUT, key, Some(content_type), bytes).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Vec<u8>, String> {
+        let response = self.send(Method::GET, key, None, Vec::new()).await?;
+        response
+            .bytes()
+            .await
+            .map(|b| b.to_vec())
+            .map_err(|e| format!("Failed to read S3 response: {}", e))
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), String> {
+        self.send(Method::DELETE, key, None, Vec::new()).await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::HashMap, sync::Mutex};
+
+    use axum::{Router, body::Bytes, extract::State, http::{HeaderMap, Method as HttpMethod, StatusCode, Uri}, response::{IntoResponse, Response}};
+
+    use super::*;
+
+    const ACCESS_KEY: &str = "test-access";
+    const SECRET_KEY: &str = "test-secret";
+
+    // An S3 stand-in that checks each request's SigV4 signature before serving objects from memory
+    #[derive(Default)]
+    struct MockS3 {
+        objects: Mutex<HashMap<String, (String, Vec<u8>)>>, // path -> (content type, bytes)
+    }
+
+    fn header<'a>(headers: &'a HeaderMap, name: &str) -> &'a str {
+        headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or_default()
+    }
+
+    fn signature_ok(method: &HttpMethod, path: &str, headers: &HeaderMap, body: &[u8]) -> bool {
+        let amz_date = header(headers, "x-amz-date");
+        let payload_hash = header(headers, "x-amz-content-sha256");
+        if payload_hash != hex::encode(Sha256::digest(body)) || amz_date.len() < 8 {
+            return false;
+        }
+
+        let scope = format!("{}/us-east-1/s3/aws4_request", &amz_date[..8]);
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\nhost;x-amz-content-sha256;x-amz-date\n{}",
+            method, path, header(headers, "host"), payload_hash, amz_date, payload_hash
+        );
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac_sha256(format!("AWS4{}", SECRET_KEY).as_bytes(), &amz_date[..8]);
+        for part in ["us-east-1", "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let expected = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders=host;x-amz-content-sha256;x-amz-date, Signature={}",
+            ACCESS_KEY, scope, hex::encode(hmac_sha256(&key, &string_to_sign))
+        );
+        header(headers, "authorization") == expected
+    }
+
+    async fn object(State(mock): State<Arc<MockS3>>, method: HttpMethod, uri: Uri, headers: HeaderMap, body: Bytes) -> Response {
+        if !signature_ok(&method, uri.path(), &headers, &body) {
+            return StatusCode::FORBIDDEN.into_response();
+        }
+
+        let mut objects = mock.objects.lock().unwrap();
+        match method {
+            HttpMethod::PUT => {
+                objects.insert(uri.path().to_string(), (header(&headers, "content-type").to_string(), body.to_vec()));
+                StatusCode::OK.into_response()
+            }
+            HttpMethod::GET => match objects.get(uri.path()) {
+                Some((_, bytes)) => bytes.clone().into_response(),
+                None => StatusCode::NOT_FOUND.into_response(),
+            },
+            HttpMethod::DELETE => {
+                objects.remove(uri.path());
+                StatusCode::NO_CONTENT.into_response()
+            }
+            _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+        }
+    }
+
+    async fn start_s3() -> (Arc<MockS3>, String) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}/", listener.local_addr().unwrap());
+        let mock = Arc::new(MockS3::default());
+        let app = Router::new().fallback(object).with_state(mock.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (mock, endpoint)
+    }
+
+    #[tokio::test]
+    async fn s3_store_round_trips_signed_requests() {
+        let (mock, endpoint) = start_s3().await;
+        let store = S3DocumentStore::new(&endpoint, "docs", "us-east-1", ACCESS_KEY, SECRET_KEY);
+
+        store.put("app-1/order.pdf", "application/pdf", b"%PDF-1.7".to_vec()).await.unwrap();
+        assert_eq!(
+            mock.objects.lock().unwrap().get("/docs/app-1/order.pdf"),
+            Some(&("application/pdf".to_string(), b"%PDF-1.7".to_vec()))
+        );
+        assert_eq!(store.get("app-1/order.pdf").await.unwrap(), b"%PDF-1.7");
+
+        store.delete("app-1/order.pdf").await.unwrap();
+        assert!(store.get("app-1/order.pdf").await.unwrap_err().contains("404"));
+    }
+
+    #[tokio::test]
+    async fn s3_store_requests_signed_with_the_wrong_secret_are_refused() {
+        let (mock, endpoint) = start_s3().await;
+        let store = S3DocumentStore::new(&endpoint, "docs", "us-east-1", ACCESS_KEY, "wrong-secret");
+
+        let err = store.put("app-1/order.pdf", "application/pdf", b"%PDF-1.7".to_vec()).await.unwrap_err();
+        assert!(err.contains("403"), "{}", err);
+        assert!(mock.objects.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn path_for_keeps_keys_inside_the_root() {
+        let store = LocalDocumentStore::new("/srv/uploads");
+        assert_eq!(store.path_for("app-1/order.pdf").unwrap(), PathBuf::from("/srv/uploads/app-1/order.pdf"));
+
+        for key in ["", "../etc/passwd", "app-1/../../etc/passwd", "./order.pdf", "/etc/passwd", "app-1//order.pdf", "app-1/"] {
+            assert!(store.path_for(key).is_err(), "{:?} was accepted", key);
+        }
+    }
+
+    #[tokio::test]
+    async fn local_store_round_trips_documents() {
+        let root = std::env::temp_dir().join(format!("bailbridge-documents-{}", uuid::Uuid::new_v4()));
+        let store = LocalDocumentStore::new(root.to_str().unwrap());
+
+        store.put("app-1/order.pdf", "application/pdf", b"%PDF-1.7".to_vec()).await.unwrap();
+        assert_eq!(store.get("app-1/order.pdf").await.unwrap(), b"%PDF-1.7");
+
+        store.delete("app-1/order.pdf").await.unwrap();
+        assert!(store.get("app-1/order.pdf").await.is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+use axum::{Json, extract::{State, Path, Query}, http::StatusCode};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    hearings::models::{
+        Hearing, HearingStatus, ScheduleHearing, AdjournHearing,
+        AdjournHearingResponse, RecordHearingOutcome, CauseListQuery, CauseListEntry
+    },
+    bail::{handlers::{fetch_application, ensure_can_view}, status::ApplicationStatus},
+    auth::jwt::Claims,
+};
+
+fn parse_actor(claims: &Claims) -> Result<Uuid, (StatusCode, String)> {
+    Uuid::parse_str(&claims.sub)
+        .map_err(|_| (StatusCode::UNAUTHORIZED, "Invalid user ID".to_string()))
+}
+
+// Clerks manage any court's hearings; judges only their own
+fn ensure_can_manage(judge_id: Option<Uuid>, claims: &Claims, actor_id: Uuid) -> Result<(), (StatusCode, String)> {
+    match claims.role.as_str() {
+        "clerk" => Ok(()),
+        "judge" if judge_id == Some(actor_id) => Ok(()),
+        "judge" => Err((StatusCode::FORBIDDEN, "Case is not assigned to you".to_string())),
+        _ => Err((StatusCode::FORBIDDEN, "Only judges and court clerks can manage hearings".to_string())),
+    }
+}
+
+async fn fetch_hearing(db: &DbPool, hearing_id: Uuid) -> Result<Hearing, (StatusCode, String)> {
+    sqlx::query_as::<_, Hearing>("SELECT * FROM hearings WHERE id = $1")
+        .bind(hearing_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch hearing: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "Hearing not found".to_string()))
+}
+
+async fn insert_hearing( db: impl sqlx::PgExecutor<'_>, application_id: Uuid, judge_id: Option<Uuid>, payload: &ScheduleHearing, scheduled_by: Uuid, ) -> Result<Hearing, (StatusCode, String)> {
+    sqlx::query_as::<_, Hearing>(
+        r#"
+        INSERT INTO hearings (id, application_id, judge_id, court, courtroom, scheduled_at, purpose, scheduled_by)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(application_id)
+    .bind(judge_id)
+    .bind(&payload.court)
+    .bind(&payload.courtroom)
+    .bind(payload.scheduled_at)
+    .bind(&payload.purpose)
+    .bind(scheduled_by)
+    .fetch_one(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to schedule hearing: {}", e)))
+}
+
+
+
+pub async fn schedule_hearing( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<ScheduleHearing>, ) -> Result<Json<Hearing>, (StatusCode, String)> {
+
+    let actor_id = parse_actor(&claims)?;
+    let application = fetch_application(&db, &application_number).await?;
+
+    ensure_can_manage(application.judge_id, &claims, actor_id)?;
+
+    if application.status != ApplicationStatus::ListedForHearing {
+        return Err((StatusCode::CONFLICT, "Hearings can only be scheduled for cases listed for hearing".to_string()));
+    }
+
+    let hearing = insert_hearing(&db, application.id, application.judge_id, &payload, actor_id).await?;
+
+    Ok(Json(hearing))
+}
+
+
+
+pub async fn get_application_hearings( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<Vec<Hearing>>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let hearings = sqlx::query_as::<_, Hearing>(
+        "SELECT * FROM hearings WHERE application_id = $1 ORDER BY scheduled_at"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch hearings: {}", e)))?;
+
+    Ok(Json(hearings))
+}
+
+
+
+pub async fn adjourn_hearing( State(db): State<DbPool>, Path(hearing_id): Path<Uuid>, claims: Claims, Json(payload): Json<AdjournHearing>, ) -> Result<Json<AdjournHearingResponse>, (StatusCode, String)> {
+
+    let actor_id = parse_actor(&claims)?;
+    let hearing = fetch_hearing(&db, hearing_id).await?;
+
+    ensure_can_manage(hearing.judge_id, &claims, actor_id)?;
+
+    if hearing.status != HearingStatus::Scheduled {
+        return Err((StatusCode::CONFLICT, format!("Hearing is already {}", hearing.status)));
+    }
+
+    let mut tx = db.begin().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to start transaction: {}", e)))?;
+
+    let adjourned = sqlx::query_as::<_, Hearing>(
+        r#"
+        UPDATE hearings
+        SET status = $1, adjournment_reason = $2
+        WHERE id = $3 AND status = $4
+        RETURNING *
+        "#
+    )
+    .bind(HearingStatus::Adjourned)
+    .bind(&payload.reason)
+    .bind(hearing.id)
+    .bind(HearingStatus::Scheduled)
+    .fetch_optional(&mut *tx)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to adjourn hearing: {}", e)))?
+    .ok_or((StatusCode::CONFLICT, "Hearing changed concurrently, please retry".to_string()))?;
+
+    let next_hearing = match payload.next_scheduled_at {
+        Some(next_scheduled_at) => {
+            let next = ScheduleHearing {
+                court: hearing.court.clone(),
+                courtroom: hearing.courtroom.clone(),
+                scheduled_at: next_scheduled_at,
+                purpose: hearing.purpose.clone(),
+            };
+            Some(insert_hearing(&mut *tx, hearing.application_id, hearing.judge_id, &next, actor_id).await?)
+        }
+        None => None,
+    };
+
+    tx.commit().await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to commit adjournment: {}", e)))?;
+
+    Ok(Json(AdjournHearingResponse { adjourned, next_hearing }))
+}
+
+
+
+pub async fn record_hearing_outcome( State(db): State<DbPool>, Path(hearing_id): Path<Uuid>, claims: Claims, Json(payload): Json<RecordHearingOutcome>, ) -> Result<Json<Hearing>, (StatusCode, String)> {
+
+    let actor_id = parse_actor(&claims)?;
+    let hearing = fetch_hearing(&db, hearing_id).await?;
+
+    ensure_can_manage(hearing.judge_id, &claims, actor_id)?;
+
+    let hearing = sqlx::query_as::<_, Hearing>(
+        r#"
+        UPDATE hearings
+        SET status = $1, outcome = $2
+        WHERE id = $3 AND status = $4
+        RETURNING *
+        "#
+    )
+    .bind(HearingStatus::Concluded)
+    .bind(&payload.outcome)
+    .bind(hearing.id)
+    .bind(HearingStatus::Scheduled)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to record outcome: {}", e)))?
+    .ok_or((StatusCode::CONFLICT, "Only scheduled hearings can be concluded".to_string()))?;
+
+    Ok(Json(hearing))
+}
+
+
+
+pub async fn get_cause_list( State(db): State<DbPool>, _claims: Claims, Query(query): Query<CauseListQuery>, ) -> Result<Json<Vec<CauseListEntry>>, (StatusCode, String)> {
+
+    if query.judge_id.is_none() && query.court.is_none() {
+        return Err((StatusCode::BAD_REQUEST, "Specify a judge_id or a court".to_string()));
+    }
+
+    let entries = sqlx::query_as::<_, CauseListEntry>(
+        r#"
+        SELECT a.application_number, a.applicant_name, a.fir_number, a.bail_type, h.*
+        FROM hearings h
+        JOIN bail_applications a ON a.id = h.application_id
+        WHERE h.scheduled_at::date = $1
+          AND ($2::uuid IS NULL OR h.judge_id = $2)
+          AND ($3::varchar IS NULL OR h.court = $3)
+        ORDER BY h.scheduled_at, h.courtroom
+        "#
+    )
+    .bind(query.date)
+    .bind(query.judge_id)
+    .bind(&query.court)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to build cause list: {}", e)))?;
+
+    Ok(Json(entries))
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, NaiveDate, Utc};
+
+use crate::db::text_enum;
+
+text_enum! {
+    pub enum HearingStatus {
+        Scheduled => "scheduled",
+        Adjourned => "adjourned",
+        Concluded => "concluded",
+    }
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Hearing {
+    pub id: Uuid,
+    pub application_id: Uuid,
+    pub judge_id: Option<Uuid>,
+    pub court: String,
+    pub courtroom: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub purpose: String,
+    pub status: HearingStatus,
+    pub adjournment_reason: Option<String>,
+    pub outcome: Option<String>,
+    pub scheduled_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ScheduleHearing {
+    pub court: String,
+    pub courtroom: String,
+    pub scheduled_at: DateTime<Utc>,
+    pub purpose: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AdjournHearing {
+    pub reason: String,
+    /// Next date, if the court fixed one when adjourning
+    pub next_scheduled_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AdjournHearingResponse {
+    pub adjourned: Hearing,
+    pub next_hearing: Option<Hearing>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RecordHearingOutcome {
+    pub outcome: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CauseListQuery {
+    pub date: NaiveDate,
+    pub judge_id: Option<Uuid>,
+    pub court: Option<String>,
+}
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct CauseListEntry {
+    pub application_number: String,
+    pub applicant_name: String,
+    pub fir_number: String,
+    pub bail_type: String,
+    #[sqlx(flatten)]
+    #[serde(flatten)]
+    pub hearing: Hearing,
+}
@@ -0,0 +1,112 @@
+use std::{path::PathBuf, sync::Arc};
+
+use async_trait::async_trait;
+use chrono::Utc;
+use lettre::{
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    transport::smtp::authentication::Credentials,
+};
+use uuid::Uuid;
+
+use crate::config::MailerConfig;
+
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+/// Outgoing mail, e.g. password reset links.
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    async fn send(&self, email: Email) -> Result<(), String>;
+}
+
+pub fn create_mailer(config: &MailerConfig) -> Arc<dyn Mailer> {
+    match config {
+        MailerConfig::File { outbox, from } => Arc::new(FileMailer::new(outbox, from)),
+        MailerConfig::Smtp { host, port, username, password, from } => Arc::new(
+            SmtpMailer::new(host, *port, username.as_deref(), password.as_deref(), from),
+        ),
+    }
+}
+
+/// Sends mail with a fire-and-forget task, so callers don't wait on the mail server.
+pub fn send_in_background(mailer: Arc<dyn Mailer>, email: Email) {
+    tokio::spawn(async move {
+        let to = email.to.clone();
+        if let Err(e) = mailer.send(email).await {
+            eprintln!("Failed to send mail to {}: {}", to, e);
+        }
+    });
+}
+
+/// Writes each message to a file in the outbox directory instead of sending it,
+/// for local development and testing.
+pub struct FileMailer {
+    outbox: PathBuf,
+    from: String,
+}
+
+impl FileMailer {
+    pub fn new(outbox: &str, from: &str) -> Self {
+        FileMailer { outbox: PathBuf::from(outbox), from: from.to_string() }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        tokio::fs::create_dir_all(&self.outbox)
+            .await
+            .map_err(|e| format!("Failed to create outbox: {}", e))?;
+
+        let path = self.outbox.join(format!("{}-{}.eml", Utc::now().format("%Y%m%dT%H%M%S"), Uuid::new_v4()));
+        let contents = format!(
+            "From: {}\nTo: {}\nSubject: {}\n\n{}\n",
+            self.from, email.to, email.subject, email.body
+        );
+        tokio::fs::write(&path, contents)
+            .await
+            .map_err(|e| format!("Failed to write mail: {}", e))?;
+
+        println!("Mail to {} written to {}", email.to, path.display());
+        Ok(())
+    }
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>,
+    from: String,
+}
+
+impl SmtpMailer {
+    pub fn new(host: &str, port: u16, username: Option<&str>, password: Option<&str>, from: &str) -> Self {
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)
+            .expect("Invalid SMTP host")
+            .port(port);
+        if let (Some(username), Some(password)) = (username, password) {
+            builder = builder.credentials(Credentials::new(username.to_string(), password.to_string()));
+        }
+
+        SmtpMailer { transport: builder.build(), from: from.to_string() }
+    }
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    async fn send(&self, email: Email) -> Result<(), String> {
+        let message = Message::builder()
+            .from(self.from.parse().map_err(|e| format!("Invalid sender address: {}", e))?)
+            .to(email.to.parse().map_err(|e| format!("Invalid recipient address: {}", e))?)
+            .subject(email.subject)
+            .body(email.body)
+            .map_err(|e| format!("Failed to build mail: {}", e))?;
+
+        self.transport
+            .send(message)
+            .await
+            .map_err(|e| format!("SMTP send failed: {}", e))?;
+        Ok(())
+    }
+}
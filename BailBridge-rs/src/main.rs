@@ -4,11 +4,18 @@ mod models;
 mod routes;
 mod auth;
 mod bail;
+mod hearings;
+mod documents;
+mod sureties;
+mod audit;
+mod admin;
+mod mailer;
+mod ratelimit;
+mod profile;
 
 use axum::{Router, routing::get};
 use dotenvy::dotenv;
 use tower_http::cors::{CorsLayer, Any};
-use tracing_subscriber;
 
 #[tokio::main]
 async fn main() {
@@ -18,6 +25,11 @@ async fn main() {
     let config = config::Config::from_env();
     let db_pool = db::create_db_pool(&config).await;
 
+    tokio::spawn(bail::compliance::run_violation_scanner(
+        db_pool.clone(),
+        std::time::Duration::from_secs(config.compliance_scan_interval_secs),
+    ));
+
     let app = Router::new()
     .route("/check", get(|| async { "BailBridge is running!" }))
     .merge(routes::create_routes(db_pool, config))
@@ -30,7 +42,7 @@ async fn main() {
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await.unwrap();
     println!("Server running on http://0.0.0.0:8080");
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(listener, app.into_make_service_with_connect_info::<std::net::SocketAddr>()).await.unwrap();
 
 
 }
\ No newline at end of file
@@ -2,14 +2,19 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 use chrono::{DateTime, Utc};
 
-// Will be used for fetching user data in protected routes
-#[allow(dead_code)]
+use crate::db::text_enum;
+
+// The account as its holder sees it under /me
 #[derive(sqlx::FromRow, Serialize)]
 pub struct User {
     pub id: Uuid,
     pub username: String,
     pub email: String,
+    pub display_name: Option<String>,
+    pub phone: Option<String>,
     pub role: String,
+    pub verification_status: VerificationStatus,
+    pub email_verified: bool,
     pub created_at: DateTime<Utc>,
 }
 
@@ -19,18 +24,154 @@ pub struct RegisterUser {
     pub email: String,
     pub password: String,
     pub role: UserRole,
+    // Required when applying as a lawyer
+    #[serde(default)]
+    pub bar_enrolment_number: Option<String>,
+}
+
+text_enum! {
+    pub enum UserRole {
+        User => "user",
+        Lawyer => "lawyer",
+        Judge => "judge",
+        Clerk => "clerk",
+        Admin => "admin",
+    }
 }
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
-#[serde(rename_all = "lowercase")]
-pub enum UserRole {
-    User,
-    Lawyer,
-    Judge,
+text_enum! {
+    pub enum VerificationStatus {
+        Pending => "pending",
+        Verified => "verified",
+        Rejected => "rejected",
+    }
 }
 
 #[derive(Deserialize)]
 pub struct LoginUser {
     pub email: String,
     pub password: String,
-}
\ No newline at end of file
+    // Required when an admin has forced a password reset
+    #[serde(default)]
+    pub new_password: Option<String>,
+}
+
+
+#[derive(Deserialize)]
+pub struct ForgotPassword {
+    pub email: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResetPassword {
+    pub token: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ChangePassword {
+    pub current_password: String,
+    pub new_password: String,
+}
+
+#[derive(Deserialize)]
+pub struct ConfirmEmail {
+    pub token: String,
+}
+
+#[derive(Deserialize)]
+pub struct ResendVerification {
+    pub email: String,
+}
+
+// A TOTP code from the authenticator app, or one of the one-time recovery codes
+#[derive(Deserialize)]
+pub struct SecondFactor {
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub recovery_code: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct MfaLogin {
+    pub challenge_token: String,
+    #[serde(flatten)]
+    pub factor: SecondFactor,
+}
+
+#[derive(Deserialize)]
+pub struct MfaSetup {
+    pub challenge_token: String,
+}
+
+// One sign-in attempt, shown to the account holder under /me/logins
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct LoginEvent {
+    pub id: Uuid,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub success: bool,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A signed-in device, shown to the account holder under /me/sessions
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Session {
+    pub id: Uuid,
+    pub user_agent: Option<String>,
+    pub ip_address: Option<String>,
+    pub last_seen_ip: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    pub current: bool, // the session making the request
+}
+
+// What the identity provider sent back to the redirect URI
+#[derive(Deserialize)]
+pub struct OidcCallback {
+    pub code: String,
+    pub state: String,
+}
+
+/// Where to send the browser to link a provider account to the signed-in user.
+#[derive(Serialize)]
+pub struct OidcLinkStart {
+    pub authorization_url: String,
+}
+
+#[derive(Deserialize)]
+pub struct CreateApiKey {
+    pub name: String,
+    // Only GET requests
+    #[serde(default)]
+    pub read_only: bool,
+    // Route patterns such as "GET /bail-applications/all"; leave out for every endpoint
+    #[serde(default)]
+    pub endpoints: Option<Vec<String>>,
+    #[serde(default)]
+    pub expires_in_days: Option<i64>,
+}
+
+// An API key as listed under /me/api-keys; the secret is never shown again after creation
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct ApiKey {
+    pub id: Uuid,
+    pub name: String,
+    pub prefix: String,
+    pub read_only: bool,
+    pub endpoints: Option<Vec<String>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub last_used_at: Option<DateTime<Utc>>,
+    pub last_used_ip: Option<String>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+pub struct CreatedApiKey {
+    #[serde(flatten)]
+    pub key: ApiKey,
+    pub api_key: String, // send as the X-Api-Key header
+}
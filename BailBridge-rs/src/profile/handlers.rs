@@ -0,0 +1,161 @@
+use axum::{Json, extract::State, http::StatusCode};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    profile::models::{Profile, ProfileRow, UpdateProfile},
+    auth::jwt::Claims,
+};
+
+const PROFILE_COLUMNS: &str =
+    "id, username, email, display_name, phone, role, verification_status, email_verified_at IS NOT NULL AS email_verified, created_at, \
+     bar_enrolment_number, practice_courts, languages, specialisations, court, designation";
+
+const MAX_LIST_ITEMS: usize = 20;
+const MAX_LIST_ITEM_LENGTH: usize = 100;
+
+fn parse_user(claims: &Claims) -> Result<Uuid, (StatusCode, String)> {
+    Uuid::parse_str(&claims.sub)
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid user ID: {}", e)))
+}
+
+// Trims a free-text field and checks its length; an empty result means "clear it"
+fn clean_text(field: &str, value: Option<String>, max_length: usize) -> Result<Option<String>, (StatusCode, String)> {
+    let Some(value) = value.map(|v| v.trim().to_string()) else {
+        return Ok(None);
+    };
+    if value.chars().count() > max_length {
+        return Err((StatusCode::BAD_REQUEST, format!("{} must be at most {} characters", field, max_length)));
+    }
+    Ok(Some(value))
+}
+
+fn clean_phone(phone: Option<String>) -> Result<Option<String>, (StatusCode, String)> {
+    let phone = clean_text("phone", phone, 20)?;
+    if let Some(phone) = phone.as_deref().filter(|p| !p.is_empty()) {
+        let digits = phone.chars().filter(char::is_ascii_digit).count();
+        let allowed = phone.chars().all(|c| c.is_ascii_digit() || " +-()".contains(c));
+        if !allowed || digits < 7 {
+            return Err((StatusCode::BAD_REQUEST, "phone must be a phone number, e.g. +91 98765 43210".to_string()));
+        }
+    }
+    Ok(phone)
+}
+
+// Trims entries, drops blanks and repeats, and caps the size of the list
+fn clean_list(field: &str, values: Option<Vec<String>>) -> Result<Option<Vec<String>>, (StatusCode, String)> {
+    let Some(values) = values else {
+        return Ok(None);
+    };
+
+    let mut cleaned: Vec<String> = Vec::new();
+    for value in values {
+        let value = value.trim();
+        if value.is_empty() || cleaned.iter().any(|v| v.eq_ignore_ascii_case(value)) {
+            continue;
+        }
+        if value.chars().count() > MAX_LIST_ITEM_LENGTH {
+            return Err((StatusCode::BAD_REQUEST, format!("Each entry in {} must be at most {} characters", field, MAX_LIST_ITEM_LENGTH)));
+        }
+        cleaned.push(value.to_string());
+    }
+
+    if cleaned.len() > MAX_LIST_ITEMS {
+        return Err((StatusCode::BAD_REQUEST, format!("{} can have at most {} entries", field, MAX_LIST_ITEMS)));
+    }
+    Ok(Some(cleaned))
+}
+
+fn ensure_role(claims: &Claims, role: &str, field: &str, present: bool) -> Result<(), (StatusCode, String)> {
+    if present && claims.role != role {
+        return Err((StatusCode::BAD_REQUEST, format!("Only {}s have a {}", role, field)));
+    }
+    Ok(())
+}
+
+
+
+pub async fn get_profile( State(db): State<DbPool>, claims: Claims, ) -> Result<Json<Profile>, (StatusCode, String)> {
+
+    let user_id = parse_user(&claims)?;
+
+    let row = sqlx::query_as::<_, ProfileRow>(&format!("SELECT {} FROM users WHERE id = $1", PROFILE_COLUMNS))
+        .bind(user_id)
+        .fetch_optional(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch profile: {}", e)))?
+        .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    Ok(Json(row.into()))
+}
+
+
+
+// A lawyer who changes their enrolment number goes back to an admin for verification
+pub async fn update_profile( State(db): State<DbPool>, claims: Claims, Json(payload): Json<UpdateProfile>, ) -> Result<Json<Profile>, (StatusCode, String)> {
+
+    let user_id = parse_user(&claims)?;
+
+    let username = clean_text("username", payload.username, 255)?;
+    if username.as_deref() == Some("") {
+        return Err((StatusCode::BAD_REQUEST, "username must not be empty".to_string()));
+    }
+    let display_name = clean_text("display_name", payload.display_name, 255)?;
+    let phone = clean_phone(payload.phone)?;
+
+    ensure_role(&claims, "lawyer", "enrolment number", payload.enrolment_number.is_some())?;
+    ensure_role(&claims, "lawyer", "list of practice courts", payload.practice_courts.is_some())?;
+    ensure_role(&claims, "lawyer", "list of languages", payload.languages.is_some())?;
+    ensure_role(&claims, "lawyer", "list of specialisations", payload.specialisations.is_some())?;
+    ensure_role(&claims, "judge", "court", payload.court.is_some())?;
+    ensure_role(&claims, "judge", "designation", payload.designation.is_some())?;
+
+    let enrolment_number = clean_text("enrolment_number", payload.enrolment_number, 100)?;
+    if enrolment_number.as_deref() == Some("") {
+        return Err((StatusCode::BAD_REQUEST, "Lawyers must keep a Bar Council enrolment number".to_string()));
+    }
+    let practice_courts = clean_list("practice_courts", payload.practice_courts)?;
+    let languages = clean_list("languages", payload.languages)?;
+    let specialisations = clean_list("specialisations", payload.specialisations)?;
+    let court = clean_text("court", payload.court, 255)?;
+    let designation = clean_text("designation", payload.designation, 100)?;
+
+    let row = sqlx::query_as::<_, ProfileRow>(&format!(
+        r#"
+        UPDATE users SET
+            username = COALESCE($2, username),
+            display_name = CASE WHEN $3::text IS NULL THEN display_name ELSE NULLIF($3, '') END,
+            phone = CASE WHEN $4::text IS NULL THEN phone ELSE NULLIF($4, '') END,
+            verification_status = CASE WHEN $5::text IS NOT NULL AND $5 IS DISTINCT FROM bar_enrolment_number THEN 'pending' ELSE verification_status END,
+            bar_enrolment_number = COALESCE($5, bar_enrolment_number),
+            practice_courts = COALESCE($6, practice_courts),
+            languages = COALESCE($7, languages),
+            specialisations = COALESCE($8, specialisations),
+            court = CASE WHEN $9::text IS NULL THEN court ELSE NULLIF($9, '') END,
+            designation = CASE WHEN $10::text IS NULL THEN designation ELSE NULLIF($10, '') END
+        WHERE id = $1
+        RETURNING {}
+        "#,
+        PROFILE_COLUMNS
+    ))
+    .bind(user_id)
+    .bind(&username)
+    .bind(&display_name)
+    .bind(&phone)
+    .bind(&enrolment_number)
+    .bind(&practice_courts)
+    .bind(&languages)
+    .bind(&specialisations)
+    .bind(&court)
+    .bind(&designation)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| match e.as_database_error().and_then(|d| d.constraint()) {
+        Some("users_username_key") => (StatusCode::CONFLICT, "Username is already taken".to_string()),
+        Some("users_bar_enrolment_number_key") => (StatusCode::CONFLICT, "Enrolment number is already registered to another account".to_string()),
+        _ => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update profile: {}", e)),
+    })?
+    .ok_or((StatusCode::NOT_FOUND, "User not found".to_string()))?;
+
+    Ok(Json(row.into()))
+}
@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::User;
+
+#[derive(sqlx::FromRow)]
+pub struct ProfileRow {
+    #[sqlx(flatten)]
+    pub user: User,
+    pub bar_enrolment_number: Option<String>,
+    pub practice_courts: Vec<String>,
+    pub languages: Vec<String>,
+    pub specialisations: Vec<String>,
+    pub court: Option<String>,
+    pub designation: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LawyerProfile {
+    pub enrolment_number: Option<String>,
+    pub practice_courts: Vec<String>,
+    pub languages: Vec<String>,
+    pub specialisations: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JudgeProfile {
+    pub court: Option<String>,
+    pub designation: Option<String>,
+}
+
+// Lawyers get a `lawyer` section and judges a `judge` section
+#[derive(Serialize)]
+pub struct Profile {
+    #[serde(flatten)]
+    pub user: User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lawyer: Option<LawyerProfile>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub judge: Option<JudgeProfile>,
+}
+
+impl From<ProfileRow> for Profile {
+    fn from(row: ProfileRow) -> Self {
+        let lawyer = (row.user.role == "lawyer").then_some(LawyerProfile {
+            enrolment_number: row.bar_enrolment_number,
+            practice_courts: row.practice_courts,
+            languages: row.languages,
+            specialisations: row.specialisations,
+        });
+        let judge = (row.user.role == "judge").then_some(JudgeProfile {
+            court: row.court,
+            designation: row.designation,
+        });
+        Profile { user: row.user, lawyer, judge }
+    }
+}
+
+// Fields left out stay as they are; an empty string clears display_name, phone, court or designation
+#[derive(Debug, Deserialize)]
+pub struct UpdateProfile {
+    pub username: Option<String>,
+    pub display_name: Option<String>,
+    pub phone: Option<String>,
+    // Lawyers only
+    pub enrolment_number: Option<String>,
+    pub practice_courts: Option<Vec<String>>,
+    pub languages: Option<Vec<String>>,
+    pub specialisations: Option<Vec<String>>,
+    // Judges only
+    pub court: Option<String>,
+    pub designation: Option<String>,
+}
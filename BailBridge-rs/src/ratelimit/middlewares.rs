@@ -0,0 +1,127 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    extract::{State, ConnectInfo},
+    http::{Request, Method, StatusCode, header},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    body::Body,
+};
+use ipnet::IpNet;
+
+use crate::{
+    audit::middlewares::client_ip,
+    auth::{api_keys, jwt::verify_jwt, keys::JwtKeys},
+    config::{Config, RateBudget, RateLimitConfig},
+    db::DbPool,
+    ratelimit::store::{create_rate_limit_store, RateLimitStore},
+};
+
+/// The configured budgets and the buckets they are drawn from.
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    db: DbPool,
+    config: RateLimitConfig,
+    jwt_keys: Arc<JwtKeys>,
+    trusted_proxies: Vec<IpNet>,
+}
+
+impl RateLimiter {
+    pub fn new(db: DbPool, config: &Config) -> Self {
+        RateLimiter {
+            store: create_rate_limit_store(&config.rate_limit.backend, db.clone()),
+            db,
+            config: config.rate_limit.clone(),
+            jwt_keys: config.jwt_keys.clone(),
+            trusted_proxies: config.trusted_proxies.clone(),
+        }
+    }
+
+    // Spends from one bucket; a broken backend lets the request through rather than taking the API down
+    async fn take(&self, key: String, budget: Option<&RateBudget>) -> Option<u64> {
+        let budget = *budget?;
+        self.store.take(&key, budget).await.unwrap_or_else(|e| {
+            eprintln!("Rate limiter unavailable: {}", e);
+            None
+        })
+    }
+}
+
+// Admin routes and document uploads draw on budgets of their own
+fn route_group(default_group: &'static str, method: &Method, path: &str) -> &'static str {
+    if path.starts_with("/admin/") {
+        "admin"
+    } else if method == Method::POST && path.ends_with("/documents") {
+        "uploads"
+    } else {
+        default_group
+    }
+}
+
+/// Token-bucket rate limiting. Signed-in callers are counted by user and live API keys
+/// by key against the route group's budget, and both by user against their role's budget.
+/// Everyone else is counted by client IP against the route group's budget alone.
+pub async fn rate_limit_middleware( State((limiter, default_group)): State<(Arc<RateLimiter>, &'static str)>, req: Request<Body>, next: Next, ) -> Response {
+
+    // Only used to pick the bucket; the handler's extractor still does the full check
+    let claims = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "))
+        .and_then(|token| verify_jwt(token, &limiter.jwt_keys).ok());
+
+    let api_key = req
+        .headers()
+        .get("X-Api-Key")
+        .and_then(|h| h.to_str().ok())
+        .map(str::to_string);
+
+    // A key only earns buckets of its own once it checks out; made-up keys share the caller's IP bucket
+    let key_holder = match (&claims, api_key) {
+        (None, Some(key)) => match api_keys::live_holder(&limiter.db, &key).await.unwrap_or(None) {
+            Some((user_id, role)) => api_keys::split_key(&key).map(|(prefix, _)| (prefix.to_string(), user_id, role)),
+            None => None,
+        },
+        _ => None,
+    };
+
+    let caller = match (&claims, &key_holder) {
+        (Some(claims), _) => format!("user:{}", claims.sub),
+        (None, Some((prefix, _, _))) => format!("key:{}", prefix),
+        (None, None) => {
+            let ip = client_ip(req.headers(), req.extensions().get::<ConnectInfo<SocketAddr>>(), &limiter.trusted_proxies);
+            format!("ip:{}", ip.as_deref().unwrap_or("unknown"))
+        }
+    };
+
+    let group = route_group(default_group, req.method(), req.uri().path());
+
+    // Role budgets are per user, so a user's keys draw on the same one as their logins
+    let role_holder = match (&claims, &key_holder) {
+        (Some(claims), _) => Some((claims.role.as_str(), claims.sub.clone())),
+        (None, Some((_, user_id, role))) => Some((role.as_str(), user_id.to_string())),
+        (None, None) => None,
+    };
+
+    let mut retry_after = limiter
+        .take(format!("{}:{}", group, caller), limiter.config.groups.get(group))
+        .await;
+    // Only spend from the role's budget on requests the group's budget lets through
+    if let (None, Some((role, user_id))) = (retry_after, role_holder) {
+        retry_after = limiter
+            .take(format!("role:{}:{}", role, user_id), limiter.config.roles.get(role))
+            .await;
+    }
+
+    if let Some(secs) = retry_after {
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, secs.to_string())],
+            format!("Too many requests, retry in {} seconds", secs),
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
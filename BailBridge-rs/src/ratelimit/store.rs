@@ -0,0 +1,198 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use async_trait::async_trait;
+
+use crate::{config::{RateBudget, RateLimitBackend}, db::DbPool};
+
+// Buckets untouched for this long have refilled completely and can be dropped
+const IDLE_BUCKET_SECS: u64 = 24 * 60 * 60;
+const PRUNE_INTERVAL_SECS: u64 = 10 * 60;
+
+/// Token buckets, one per key. `take` spends one token and returns `None` when the
+/// request may go ahead, or the number of seconds until a token is available.
+#[async_trait]
+pub trait RateLimitStore: Send + Sync {
+    async fn take(&self, key: &str, budget: RateBudget) -> Result<Option<u64>, String>;
+}
+
+pub fn create_rate_limit_store(backend: &RateLimitBackend, db: DbPool) -> Arc<dyn RateLimitStore> {
+    match backend {
+        RateLimitBackend::Memory => Arc::new(MemoryRateLimitStore::new()),
+        RateLimitBackend::Postgres => Arc::new(PostgresRateLimitStore::new(db)),
+    }
+}
+
+fn refill_rate(budget: RateBudget) -> f64 {
+    budget.requests as f64 / budget.per_secs as f64
+}
+
+fn retry_after(tokens: f64, budget: RateBudget) -> u64 {
+    ((1.0 - tokens) / refill_rate(budget)).ceil().max(1.0) as u64
+}
+
+/// Buckets held in this process; enough for a single server.
+pub struct MemoryRateLimitStore {
+    buckets: Mutex<HashMap<String, (f64, Instant)>>, // key -> (tokens, last update)
+    pruned_at: Mutex<Instant>,
+}
+
+impl MemoryRateLimitStore {
+    pub fn new() -> Self {
+        MemoryRateLimitStore {
+            buckets: Mutex::new(HashMap::new()),
+            pruned_at: Mutex::new(Instant::now()),
+        }
+    }
+
+    fn take_at(&self, key: &str, budget: RateBudget, now: Instant) -> Result<Option<u64>, String> {
+        let mut buckets = self.buckets.lock().map_err(|_| "Rate limit buckets poisoned".to_string())?;
+
+        {
+            let mut pruned_at = self.pruned_at.lock().map_err(|_| "Rate limit buckets poisoned".to_string())?;
+            if now.duration_since(*pruned_at) >= Duration::from_secs(PRUNE_INTERVAL_SECS) {
+                buckets.retain(|_, (_, updated)| now.duration_since(*updated) < Duration::from_secs(IDLE_BUCKET_SECS));
+                *pruned_at = now;
+            }
+        }
+
+        let capacity = budget.requests as f64;
+        let (tokens, updated) = buckets.entry(key.to_string()).or_insert((capacity, now));
+        *tokens = (*tokens + now.duration_since(*updated).as_secs_f64() * refill_rate(budget)).min(capacity);
+        *updated = now;
+
+        if *tokens >= 1.0 {
+            *tokens -= 1.0;
+            Ok(None)
+        } else {
+            Ok(Some(retry_after(*tokens, budget)))
+        }
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for MemoryRateLimitStore {
+    async fn take(&self, key: &str, budget: RateBudget) -> Result<Option<u64>, String> {
+        self.take_at(key, budget, Instant::now())
+    }
+}
+
+/// Buckets in the `rate_limit_buckets` table, so every server behind a load
+/// balancer draws on the same budget. Each take is a single upsert.
+pub struct PostgresRateLimitStore {
+    db: DbPool,
+    pruned_at: Mutex<Instant>,
+}
+
+impl PostgresRateLimitStore {
+    pub fn new(db: DbPool) -> Self {
+        PostgresRateLimitStore { db, pruned_at: Mutex::new(Instant::now()) }
+    }
+
+    fn prune_due(&self) -> bool {
+        let Ok(mut pruned_at) = self.pruned_at.lock() else {
+            return false;
+        };
+        if pruned_at.elapsed() < Duration::from_secs(PRUNE_INTERVAL_SECS) {
+            return false;
+        }
+        *pruned_at = Instant::now();
+        true
+    }
+}
+
+#[async_trait]
+impl RateLimitStore for PostgresRateLimitStore {
+    async fn take(&self, key: &str, budget: RateBudget) -> Result<Option<u64>, String> {
+        if self.prune_due() {
+            sqlx::query("DELETE FROM rate_limit_buckets WHERE updated_at < NOW() - make_interval(secs => $1)")
+                .bind(IDLE_BUCKET_SECS as f64)
+                .execute(&self.db)
+                .await
+                .map_err(|e| format!("Failed to prune rate limit buckets: {}", e))?;
+        }
+
+        // SET expressions all see the row as it was before this update
+        let (allowed, tokens): (bool, f64) = sqlx::query_as(
+            r#"
+            INSERT INTO rate_limit_buckets AS b (key, tokens, allowed, updated_at)
+            VALUES ($1, $2 - 1, TRUE, NOW())
+            ON CONFLICT (key) DO UPDATE SET
+                allowed = LEAST($2, b.tokens + EXTRACT(EPOCH FROM NOW() - b.updated_at) * $3) >= 1,
+                tokens = LEAST($2, b.tokens + EXTRACT(EPOCH FROM NOW() - b.updated_at) * $3)
+                    - CASE WHEN LEAST($2, b.tokens + EXTRACT(EPOCH FROM NOW() - b.updated_at) * $3) >= 1 THEN 1 ELSE 0 END,
+                updated_at = NOW()
+            RETURNING allowed, tokens
+            "#,
+        )
+        .bind(key)
+        .bind(budget.requests as f64)
+        .bind(refill_rate(budget))
+        .fetch_one(&self.db)
+        .await
+        .map_err(|e| format!("Failed to take from rate limit bucket: {}", e))?;
+
+        Ok(if allowed { None } else { Some(retry_after(tokens, budget)) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUDGET: RateBudget = RateBudget { requests: 3, per_secs: 60 };
+
+    #[test]
+    fn retry_after_is_the_time_to_the_next_whole_token() {
+        assert_eq!(retry_after(0.0, BUDGET), 20);
+        assert_eq!(retry_after(0.5, BUDGET), 10);
+        assert_eq!(retry_after(0.99, BUDGET), 1);
+        assert_eq!(retry_after(0.0, RateBudget { requests: 600, per_secs: 60 }), 1);
+        assert_eq!(retry_after(0.0, RateBudget { requests: 1, per_secs: 3600 }), 3600);
+    }
+
+    #[test]
+    fn full_bucket_allows_a_burst_then_refuses() {
+        let store = MemoryRateLimitStore::new();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert_eq!(store.take_at("ip:a", BUDGET, now).unwrap(), None);
+        }
+        assert_eq!(store.take_at("ip:a", BUDGET, now).unwrap(), Some(20));
+        assert_eq!(store.take_at("ip:a", BUDGET, now + Duration::from_secs(5)).unwrap(), Some(15));
+    }
+
+    #[test]
+    fn bucket_refills_evenly_up_to_capacity() {
+        let store = MemoryRateLimitStore::new();
+        let now = Instant::now();
+        for _ in 0..3 {
+            store.take_at("ip:a", BUDGET, now).unwrap();
+        }
+
+        let later = now + Duration::from_secs(20);
+        assert_eq!(store.take_at("ip:a", BUDGET, later).unwrap(), None);
+        assert_eq!(store.take_at("ip:a", BUDGET, later).unwrap(), Some(20));
+
+        // A long wait refills the bucket to its size, not beyond
+        let much_later = later + Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert_eq!(store.take_at("ip:a", BUDGET, much_later).unwrap(), None);
+        }
+        assert!(store.take_at("ip:a", BUDGET, much_later).unwrap().is_some());
+    }
+
+    #[test]
+    fn keys_have_separate_buckets() {
+        let store = MemoryRateLimitStore::new();
+        let now = Instant::now();
+        for _ in 0..3 {
+            store.take_at("ip:a", BUDGET, now).unwrap();
+        }
+        assert!(store.take_at("ip:a", BUDGET, now).unwrap().is_some());
+        assert_eq!(store.take_at("ip:b", BUDGET, now).unwrap(), None);
+    }
+}
@@ -1,30 +1,145 @@
-use axum::{Router, routing::{post, get}};
+use std::sync::Arc;
+
+use axum::{Router, routing::{post, get, put, delete}, extract::DefaultBodyLimit, middleware};
 use crate::{
-    auth::handlers::{register_user, login_user},
+    auth::{
+        handlers::{
+            register_user, login_user, refresh_access_token, logout, logout_all,
+            forgot_password, reset_password, confirm_email, resend_verification_email,
+            verify_login_challenge, setup_login_totp, start_totp_enrolment, confirm_totp_enrolment,
+            regenerate_recovery_codes, disable_totp, get_login_history, get_jwks,
+            start_oidc_login, finish_oidc_login, start_oidc_link, create_api_key, list_api_keys, revoke_api_key,
+            get_sessions, revoke_session, change_password
+        },
+        oidc::OidcClient,
+        revocation::RevocationStore,
+    },
     bail::handlers::{
         create_bail_application, get_bail_application, 
         get_my_bail_applications, get_all_bail_applications_for_lawyer,
-        assign_lawyer_to_case
+        assign_lawyer_to_case, transition_bail_application,
+        get_judge_bail_applications, take_up_bail_application, decide_bail_application,
+        search_bail_conditions, record_check_in, verify_check_in, get_compliance_timeline,
+        create_draft, get_my_drafts, get_draft, update_draft, submit_draft, discard_draft,
+        update_bail_application, withdraw_bail_application, get_application_revisions
+    },
+    hearings::handlers::{
+        schedule_hearing, get_application_hearings, adjourn_hearing,
+        record_hearing_outcome, get_cause_list
+    },
+    documents::{
+        handlers::{upload_document, list_documents, download_document},
+        store::create_document_store,
     },
+    profile::handlers::{get_profile, update_profile},
+    sureties::handlers::{add_surety, list_sureties, update_surety, delete_surety},
+    admin::handlers::{
+        search_users, get_user, get_user_actions, create_user, set_verification_status,
+        suspend_user, reactivate_user, change_user_role, force_password_reset, revoke_user_tokens,
+        unlock_user
+    },
+    audit::{
+        handlers::{query_audit_log, verify_audit_log},
+        middlewares::audit_middleware,
+    },
+    mailer::create_mailer,
+    ratelimit::middlewares::{rate_limit_middleware, RateLimiter},
     db::DbPool,
     config::Config,
 };
 
 pub fn create_routes(db: DbPool, config: Config) -> Router {
 
+    let revocations = Arc::new(RevocationStore::new(db.clone(), &config));
+    let mailer = create_mailer(&config.mailer);
+    let rate_limiter = Arc::new(RateLimiter::new(db.clone(), &config));
+    let oidc = Arc::new(OidcClient::new());
+
     let public_routes = Router::new()
         .route("/register", post(register_user))
         .route("/login", post(login_user))
+        .route("/login/mfa", post(verify_login_challenge))
+        .route("/login/mfa/setup", post(setup_login_totp))
+        .route("/token/refresh", post(refresh_access_token))
+        .route("/password/forgot", post(forgot_password))
+        .route("/password/reset", post(reset_password))
+        .route("/email/verify", post(confirm_email))
+        .route("/email/verify/resend", post(resend_verification_email))
+        .route("/.well-known/jwks.json", get(get_jwks))
+        .route("/auth/oidc/{provider}/login", get(start_oidc_login))
+        .route("/auth/oidc/{provider}/callback", post(finish_oidc_login))
+        .layer(middleware::from_fn_with_state((rate_limiter.clone(), "auth"), rate_limit_middleware))
+        .layer(middleware::from_fn_with_state(db.clone(), audit_middleware))
+        .layer(axum::Extension(revocations.clone()))
+        .layer(axum::Extension(mailer.clone()))
+        .layer(axum::Extension(oidc.clone()))
+        .layer(axum::Extension(config.clone()))
+        .layer(axum::Extension(db.clone()))
         .with_state((db.clone(), config.clone()));
 
-    
+    let document_store = create_document_store(&config.document_store);
+    // Leave room for the multipart framing around the file itself
+    let upload_limit = DefaultBodyLimit::max(config.max_document_size_bytes + 64 * 1024);
+
     let protected_routes = Router::new()
+        .route("/logout", post(logout))
+        .route("/logout/all", post(logout_all))
+        .route("/me", get(get_profile).patch(update_profile))
+        .route("/me/password", post(change_password))
+        .route("/me/mfa/totp", post(start_totp_enrolment).delete(disable_totp))
+        .route("/me/mfa/totp/confirm", post(confirm_totp_enrolment))
+        .route("/me/mfa/recovery-codes", post(regenerate_recovery_codes))
+        .route("/me/logins", get(get_login_history))
+        .route("/me/sessions", get(get_sessions))
+        .route("/me/sessions/{session_id}", delete(revoke_session))
+        .route("/me/api-keys", post(create_api_key).get(list_api_keys))
+        .route("/me/api-keys/{key_id}", delete(revoke_api_key))
+        .route("/me/identities/{provider}", post(start_oidc_link))
         .route("/bail-applications", post(create_bail_application))
         .route("/bail-applications/my", get(get_my_bail_applications))
         .route("/bail-applications/all", get(get_all_bail_applications_for_lawyer))
-        .route("/bail-applications/:application_number", get(get_bail_application))
-        .route("/bail-applications/:application_number/assign", post(assign_lawyer_to_case))
+        .route("/bail-applications/judge", get(get_judge_bail_applications))
+        .route("/bail-applications/drafts", post(create_draft).get(get_my_drafts))
+        .route("/bail-applications/drafts/{application_number}", get(get_draft).patch(update_draft).delete(discard_draft))
+        .route("/bail-applications/drafts/{application_number}/submit", post(submit_draft))
+        .route("/bail-applications/{application_number}", get(get_bail_application).patch(update_bail_application))
+        .route("/bail-applications/{application_number}/assign", post(assign_lawyer_to_case))
+        .route("/bail-applications/{application_number}/transitions", post(transition_bail_application))
+        .route("/bail-applications/{application_number}/withdraw", post(withdraw_bail_application))
+        .route("/bail-applications/{application_number}/revisions", get(get_application_revisions))
+        .route("/bail-applications/{application_number}/take-up", post(take_up_bail_application))
+        .route("/bail-applications/{application_number}/decision", post(decide_bail_application))
+        .route("/bail-applications/{application_number}/check-ins", post(record_check_in).get(get_compliance_timeline))
+        .route("/bail-applications/{application_number}/check-ins/{check_in_id}/verify", post(verify_check_in))
+        .route("/bail-applications/{application_number}/hearings", post(schedule_hearing).get(get_application_hearings))
+        .route("/bail-applications/{application_number}/documents", post(upload_document).layer(upload_limit).get(list_documents))
+        .route("/bail-applications/{application_number}/documents/{document_id}", get(download_document))
+        .route("/bail-applications/{application_number}/sureties", post(add_surety).get(list_sureties))
+        .route("/bail-applications/{application_number}/sureties/{surety_id}", put(update_surety).delete(delete_surety))
+        .route("/bail-conditions", get(search_bail_conditions))
+        .route("/hearings/{hearing_id}/adjourn", post(adjourn_hearing))
+        .route("/hearings/{hearing_id}/outcome", post(record_hearing_outcome))
+        .route("/cause-list", get(get_cause_list))
+        .route("/admin/users", post(create_user).get(search_users))
+        .route("/admin/users/{user_id}", get(get_user))
+        .route("/admin/users/{user_id}/actions", get(get_user_actions))
+        .route("/admin/users/{user_id}/verification", post(set_verification_status))
+        .route("/admin/users/{user_id}/suspend", post(suspend_user))
+        .route("/admin/users/{user_id}/reactivate", post(reactivate_user))
+        .route("/admin/users/{user_id}/unlock", post(unlock_user))
+        .route("/admin/users/{user_id}/role", put(change_user_role))
+        .route("/admin/users/{user_id}/password-reset", post(force_password_reset))
+        .route("/admin/users/{user_id}/revoke-tokens", post(revoke_user_tokens))
+        .route("/admin/audit", get(query_audit_log))
+        .route("/admin/audit/verify", get(verify_audit_log))
+        .layer(middleware::from_fn_with_state((rate_limiter, "api"), rate_limit_middleware))
+        .layer(middleware::from_fn_with_state(db.clone(), audit_middleware))
+        .layer(axum::Extension(document_store))
+        .layer(axum::Extension(revocations))
+        .layer(axum::Extension(mailer))
+        .layer(axum::Extension(oidc))
         .layer(axum::Extension(config))
+        .layer(axum::Extension(db.clone()))
         .with_state(db);
 
     Router::new()
@@ -0,0 +1,163 @@
+use axum::{Json, Extension, extract::{State, Path}, http::StatusCode};
+use uuid::Uuid;
+
+use crate::{
+    db::DbPool,
+    sureties::models::{Surety, SuretyInput, SuretyResponse},
+    bail::{
+        handlers::{fetch_application, ensure_can_view},
+        models::BailApplication,
+        status::ApplicationStatus,
+    },
+    auth::jwt::Claims,
+    config::Config,
+};
+
+// ID numbers are compared across applications, so store them in one canonical form
+fn normalize_id_number(id_document_number: &str) -> String {
+    id_document_number
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '-')
+        .collect::<String>()
+        .to_uppercase()
+}
+
+fn ensure_can_edit(application: &BailApplication, claims: &Claims) -> Result<(), (StatusCode, String)> {
+    let user_id = ensure_can_view(application, claims)?;
+
+    if application.user_id != user_id && application.assigned_lawyer_id != Some(user_id) {
+        return Err((StatusCode::FORBIDDEN, "Only the applicant or assigned lawyer can manage sureties".to_string()));
+    }
+
+    if matches!(application.status, ApplicationStatus::Approved | ApplicationStatus::Rejected | ApplicationStatus::Withdrawn) {
+        return Err((StatusCode::CONFLICT, format!("Sureties cannot be changed once the application is {}", application.status)));
+    }
+    Ok(())
+}
+
+async fn surety_warning(db: &DbPool, config: &Config, surety: &Surety) -> Result<Option<String>, (StatusCode, String)> {
+    let active_bails: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(DISTINCT s.application_id)
+        FROM sureties s
+        JOIN bail_applications a ON a.id = s.application_id
+        WHERE s.id_document_number = $1
+          AND a.status IN ('pending', 'under_review', 'listed_for_hearing', 'approved')
+        "#
+    )
+    .bind(&surety.id_document_number)
+    .fetch_one(db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to check surety: {}", e)))?;
+
+    if active_bails > config.max_active_bails_per_surety {
+        return Ok(Some(format!(
+            "Surety with ID {} stands for {} active bails (limit {})",
+            surety.id_document_number, active_bails, config.max_active_bails_per_surety
+        )));
+    }
+    Ok(None)
+}
+
+
+
+pub async fn add_surety( State(db): State<DbPool>, Extension(config): Extension<Config>, Path(application_number): Path<String>, claims: Claims, Json(payload): Json<SuretyInput>, ) -> Result<Json<SuretyResponse>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_edit(&application, &claims)?;
+
+    let surety = sqlx::query_as::<_, Surety>(
+        r#"
+        INSERT INTO sureties (id, application_id, name, relation, address, id_document_number, solvency_value, phone_number)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING *
+        "#
+    )
+    .bind(Uuid::new_v4())
+    .bind(application.id)
+    .bind(&payload.name)
+    .bind(&payload.relation)
+    .bind(&payload.address)
+    .bind(normalize_id_number(&payload.id_document_number))
+    .bind(payload.solvency_value)
+    .bind(&payload.phone_number)
+    .fetch_one(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to add surety: {}", e)))?;
+
+    let warning = surety_warning(&db, &config, &surety).await?;
+
+    Ok(Json(SuretyResponse { surety, warning }))
+}
+
+
+
+pub async fn list_sureties( State(db): State<DbPool>, Path(application_number): Path<String>, claims: Claims, ) -> Result<Json<Vec<Surety>>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_view(&application, &claims)?;
+
+    let sureties = sqlx::query_as::<_, Surety>(
+        "SELECT * FROM sureties WHERE application_id = $1 ORDER BY created_at"
+    )
+    .bind(application.id)
+    .fetch_all(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to fetch sureties: {}", e)))?;
+
+    Ok(Json(sureties))
+}
+
+
+
+pub async fn update_surety( State(db): State<DbPool>, Extension(config): Extension<Config>, Path((application_number, surety_id)): Path<(String, Uuid)>, claims: Claims, Json(payload): Json<SuretyInput>, ) -> Result<Json<SuretyResponse>, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_edit(&application, &claims)?;
+
+    let surety = sqlx::query_as::<_, Surety>(
+        r#"
+        UPDATE sureties
+        SET name = $1, relation = $2, address = $3, id_document_number = $4, solvency_value = $5, phone_number = $6
+        WHERE id = $7 AND application_id = $8
+        RETURNING *
+        "#
+    )
+    .bind(&payload.name)
+    .bind(&payload.relation)
+    .bind(&payload.address)
+    .bind(normalize_id_number(&payload.id_document_number))
+    .bind(payload.solvency_value)
+    .bind(&payload.phone_number)
+    .bind(surety_id)
+    .bind(application.id)
+    .fetch_optional(&db)
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to update surety: {}", e)))?
+    .ok_or((StatusCode::NOT_FOUND, "Surety not found".to_string()))?;
+
+    let warning = surety_warning(&db, &config, &surety).await?;
+
+    Ok(Json(SuretyResponse { surety, warning }))
+}
+
+
+
+pub async fn delete_surety( State(db): State<DbPool>, Path((application_number, surety_id)): Path<(String, Uuid)>, claims: Claims, ) -> Result<StatusCode, (StatusCode, String)> {
+
+    let application = fetch_application(&db, &application_number).await?;
+    ensure_can_edit(&application, &claims)?;
+
+    let result = sqlx::query("DELETE FROM sureties WHERE id = $1 AND application_id = $2")
+        .bind(surety_id)
+        .bind(application.id)
+        .execute(&db)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to delete surety: {}", e)))?;
+
+    if result.rows_affected() == 0 {
+        return Err((StatusCode::NOT_FOUND, "Surety not found".to_string()));
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use chrono::{DateTime, Utc};
+
+#[derive(Debug, Serialize, sqlx::FromRow)]
+pub struct Surety {
+    pub id: Uuid,
+    pub application_id: Uuid,
+    pub name: String,
+    pub relation: String,
+    pub address: String,
+    pub id_document_number: String,
+    pub solvency_value: i64,
+    pub phone_number: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SuretyInput {
+    pub name: String,
+    pub relation: String,
+    pub address: String,
+    pub id_document_number: String,
+    pub solvency_value: i64,
+    pub phone_number: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SuretyResponse {
+    #[serde(flatten)]
+    pub surety: Surety,
+    /// Set when this surety already stands for too many active bails
+    pub warning: Option<String>,
+}